@@ -0,0 +1,74 @@
+// Generates the tetromino consts of tetrominos.rs from the forms drawn in tetrominos.txt.
+use std::env;
+use std::fs;
+use std::path;
+
+// The colours in the format are for custom piece sets, the client colours the tetrominos.
+#[allow(dead_code)]
+#[path = "src/tetris/shape_text.rs"]
+mod shape_text;
+
+// The tetrominos::Kind variants tetrominos.txt defines.
+const KINDS: [&str; 7] = [
+    "Stick", "Seven", "Hook", "Square", "Snake", "Pyramid", "Zig",
+];
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let source = path::Path::new(&manifest_dir).join("tetrominos.txt");
+    println!("cargo:rerun-if-changed=tetrominos.txt");
+    println!("cargo:rerun-if-changed=src/tetris/shape_text.rs");
+
+    let text = fs::read_to_string(&source)
+        .unwrap_or_else(|e| panic!("reading {}: {}", source.display(), e));
+
+    // A bad definition fails the build with the line of tetrominos.txt it is on.
+    let code = match shape_text::parse(&text).and_then(|definitions| generate(&definitions)) {
+        Ok(code) => code,
+        Err(e) => format!("compile_error!({:?});\n", format!("tetrominos.txt {}", e)),
+    };
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(path::Path::new(&out_dir).join("tetrominos.rs"), code).unwrap();
+}
+
+fn generate(definitions: &[shape_text::Definition]) -> Result<String, String> {
+    let mut code = String::new();
+    for definition in definitions {
+        if !KINDS.contains(&definition.name.as_str()) {
+            return Err(format!(
+                "line {}: {} is not a tetromino, expected one of {}",
+                definition.line,
+                definition.name,
+                KINDS.join(", ")
+            ));
+        }
+        if definition.forms[0].len() != 4 {
+            return Err(format!(
+                "line {}: {}: tetromino forms are 4x4",
+                definition.line, definition.name
+            ));
+        }
+
+        code += &format!(
+            "const {}: Tetromino = Tetromino {{\n    kind: Kind::{},\n    forms: [\n",
+            definition.name.to_uppercase(),
+            definition.name
+        );
+        for form in definition.forms.iter() {
+            code += "        Form::new([\n";
+            for row in form.iter() {
+                code += &format!("            {:?},\n", row);
+            }
+            code += "        ]),\n";
+        }
+        code += "    ],\n};\n\n";
+    }
+    for kind in KINDS {
+        if !definitions.iter().any(|definition| definition.name == kind) {
+            return Err(format!("{} is missing", kind));
+        }
+    }
+
+    Ok(code)
+}
@@ -0,0 +1,8 @@
+// The game engine without any SDL (or other platform) dependencies: the simulation,
+// rules, scoring, recordings and replays. The SDL client is one consumer of it.
+pub mod netplay;
+pub mod recording_file;
+pub mod replays;
+pub mod tetris;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,547 @@
+use std::collections;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::net;
+use std::sync::mpsc;
+use std::thread;
+use std::time;
+
+use crate::tetris::actions;
+use crate::tetris::game;
+use crate::tetris::rules;
+use crate::tetris::versus;
+
+use serde::{Deserialize, Serialize};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// Ticks between state hash checks, a quarter of a second at the client tick rate.
+const HASH_INTERVAL: usize = 60;
+
+// How long the relay waits for the hello of a client that connected.
+const HELLO_TIMEOUT: time::Duration = time::Duration::from_secs(10);
+
+// Messages are sent as JSON, one message per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Message {
+    // Client to relay when connecting, the match is played with the rules, seeds and
+    // input delay of the player that connected first.
+    Hello {
+        version: u32,
+        rules: rules::Rules,
+        seeds: [u64; 2],
+        input_delay: usize,
+    },
+    // Relay to both clients once the second player has connected.
+    Start {
+        player: usize,
+        rules: rules::Rules,
+        seeds: [u64; 2],
+        input_delay: usize,
+    },
+    // The actions a player queued for a tick. It is sent for every tick, also the ones
+    // without actions, the opponent can only simulate the tick once it has it.
+    Input {
+        tick: usize,
+        actions: Vec<actions::Action>,
+    },
+    // The garbage the sender's game sent on a tick.
+    Garbage {
+        tick: usize,
+        lines: u32,
+    },
+    // The state hash of both games after a tick, see Game::state_hash.
+    Hash {
+        tick: usize,
+        hash: u64,
+    },
+}
+
+// The rules, seeds and input delay a client sent in its hello.
+type Hello = (rules::Rules, [u64; 2], usize);
+
+struct Connection {
+    reader: io::BufReader<net::TcpStream>,
+    writer: net::TcpStream,
+    // A partly received line, non-blocking reads can stop anywhere in a message.
+    line: String,
+    // What a non-blocking connection could not send yet, sent before the next message.
+    pending: Vec<u8>,
+}
+
+impl Connection {
+    fn new(stream: net::TcpStream) -> Result<Connection, String> {
+        stream.set_nodelay(true).map_err(|e| e.to_string())?;
+        let writer = stream.try_clone().map_err(|e| e.to_string())?;
+
+        Ok(Connection {
+            reader: io::BufReader::new(stream),
+            writer,
+            line: String::new(),
+            pending: vec![],
+        })
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> Result<(), String> {
+        self.writer
+            .set_nonblocking(nonblocking)
+            .map_err(|e| e.to_string())
+    }
+
+    fn send(&mut self, msg: &Message) -> Result<(), String> {
+        let mut json = serde_json::to_string(msg).map_err(|e| e.to_string())?;
+        json.push('\n');
+        self.pending.extend_from_slice(json.as_bytes());
+        self.flush()
+    }
+
+    // Sends as much of the pending messages as the socket takes, the rest stays pending
+    // when a non-blocking socket's buffer is full.
+    fn flush(&mut self) -> Result<(), String> {
+        while !self.pending.is_empty() {
+            match self.writer.write(&self.pending) {
+                Ok(0) => return Err("opponent disconnected".to_string()),
+                Ok(n) => {
+                    self.pending.drain(..n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        Ok(())
+    }
+
+    // Whether the other end is still connected, clients send nothing while they wait
+    // for a match so anything other than the end of the stream means it is.
+    fn is_connected(&self) -> bool {
+        if self.set_nonblocking(true).is_err() {
+            return false;
+        }
+        let connected = match self.writer.peek(&mut [0]) {
+            Ok(0) => false,
+            Ok(_) => true,
+            Err(e) => e.kind() == io::ErrorKind::WouldBlock,
+        };
+        connected && self.set_nonblocking(false).is_ok()
+    }
+
+    // The next message, None if a non-blocking connection has no complete message yet.
+    fn recv(&mut self) -> Result<Option<Message>, String> {
+        loop {
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return Err("opponent disconnected".to_string()),
+                Ok(_) => {
+                    if self.line.ends_with('\n') {
+                        let msg = serde_json::from_str(&self.line).map_err(|e| e.to_string());
+                        self.line.clear();
+                        return msg.map(Some);
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+
+    fn recv_hello(&mut self) -> Result<Hello, String> {
+        match self.recv()? {
+            Some(Message::Hello {
+                version,
+                rules,
+                seeds,
+                input_delay,
+            }) if version == PROTOCOL_VERSION => Ok((rules, seeds, input_delay)),
+            Some(Message::Hello { version, .. }) => Err(format!(
+                "protocol version {} is not supported, expected {}",
+                version, PROTOCOL_VERSION
+            )),
+            _ => Err("expected a hello".to_string()),
+        }
+    }
+}
+
+// Reads the hello of a client that connected, clients that send none in time are
+// disconnected.
+fn handshake(stream: net::TcpStream) -> Result<(Connection, Hello), String> {
+    stream
+        .set_read_timeout(Some(HELLO_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let mut conn = Connection::new(stream)?;
+    let hello = conn.recv_hello()?;
+    conn.writer
+        .set_read_timeout(None)
+        .map_err(|e| e.to_string())?;
+
+    Ok((conn, hello))
+}
+
+// Pairs up the clients in the order their hellos arrive, and relays every message
+// between the two players of a match. Every hello is read and every match is relayed
+// on its own thread, so a slow or silent client holds up nobody else.
+pub fn serve(listener: net::TcpListener) -> Result<(), String> {
+    let (clients, hellos) = mpsc::channel();
+    thread::spawn(move || pair(hellos));
+
+    loop {
+        let (stream, addr) = listener.accept().map_err(|e| e.to_string())?;
+        let clients = clients.clone();
+        thread::spawn(move || match handshake(stream) {
+            Ok(client) => {
+                let _ = clients.send(client);
+            }
+            Err(e) => println!("{}: {}", addr, e),
+        });
+    }
+}
+
+fn pair(clients: mpsc::Receiver<(Connection, Hello)>) {
+    let mut waiting: Option<(Connection, Hello)> = None;
+    for (conn, hello) in clients {
+        // A client that left while it was waiting for an opponent is replaced.
+        match waiting.take() {
+            Some((conn_1, hello)) if conn_1.is_connected() => {
+                thread::spawn(move || {
+                    if let Err(e) = relay(conn_1, conn, hello) {
+                        println!("match ended: {}", e)
+                    }
+                });
+            }
+            _ => waiting = Some((conn, hello)),
+        }
+    }
+}
+
+fn relay(mut conn_1: Connection, mut conn_2: Connection, hello: Hello) -> Result<(), String> {
+    let (rules, seeds, input_delay) = hello;
+    for (player, conn) in [&mut conn_1, &mut conn_2].into_iter().enumerate() {
+        conn.send(&Message::Start {
+            player,
+            rules: rules.clone(),
+            seeds,
+            input_delay,
+        })?;
+    }
+
+    let mut writer_1 = conn_1.writer.try_clone().map_err(|e| e.to_string())?;
+    let writer_2 = conn_2.writer.try_clone().map_err(|e| e.to_string())?;
+
+    // When either player leaves the other one is disconnected too.
+    let forward = thread::spawn(move || {
+        let _ = io::copy(&mut conn_1.reader, &mut conn_2.writer);
+        let _ = conn_2.writer.shutdown(net::Shutdown::Both);
+    });
+    let _ = io::copy(&mut conn_2.reader, &mut writer_1);
+    let _ = writer_1.shutdown(net::Shutdown::Both);
+    let _ = writer_2.shutdown(net::Shutdown::Both);
+    let _ = forward.join();
+
+    Ok(())
+}
+
+// Both clients simulate both games of the match. The local player's actions are sent
+// to the opponent `input_delay` ticks before they run, so a tick can usually be
+// simulated as soon as it is due. When the opponent's input for a tick has not arrived
+// yet the simulation waits for it.
+pub struct Lockstep {
+    connection: Connection,
+    pub player: usize,
+    rules: rules::Rules,
+    seeds: [u64; 2],
+    input_delay: usize,
+    // The next tick to simulate.
+    tick: usize,
+    // The next tick to send the local input for.
+    send_tick: usize,
+    queued_actions: Vec<actions::Action>,
+    inputs: [collections::BTreeMap<usize, Vec<actions::Action>>; 2],
+    // The garbage the opponent sent according to the local simulation and according
+    // to the opponent, compared when the hashes are.
+    local_garbage: collections::BTreeMap<usize, u32>,
+    remote_garbage: collections::BTreeMap<usize, u32>,
+    local_hashes: collections::BTreeMap<usize, u64>,
+    remote_hashes: collections::BTreeMap<usize, u64>,
+}
+
+// Connects to a relay and waits for an opponent, see Lockstep::new_match.
+pub fn connect(
+    addr: &str,
+    rules: rules::Rules,
+    seeds: [u64; 2],
+    input_delay: usize,
+) -> Result<Lockstep, String> {
+    let stream = net::TcpStream::connect(addr).map_err(|e| e.to_string())?;
+    let mut connection = Connection::new(stream)?;
+    connection.send(&Message::Hello {
+        version: PROTOCOL_VERSION,
+        rules,
+        seeds,
+        input_delay,
+    })?;
+
+    let Some(Message::Start {
+        player,
+        rules,
+        seeds,
+        input_delay,
+    }) = connection.recv()?
+    else {
+        return Err("expected the match to start".to_string());
+    };
+    connection.set_nonblocking(true)?;
+
+    Ok(Lockstep {
+        connection,
+        player,
+        rules,
+        seeds,
+        input_delay,
+        tick: 0,
+        send_tick: input_delay,
+        queued_actions: vec![],
+        inputs: [collections::BTreeMap::new(), collections::BTreeMap::new()],
+        local_garbage: collections::BTreeMap::new(),
+        remote_garbage: collections::BTreeMap::new(),
+        local_hashes: collections::BTreeMap::new(),
+        remote_hashes: collections::BTreeMap::new(),
+    })
+}
+
+impl Lockstep {
+    // The match is played with the rules and seeds of the player that connected first.
+    pub fn new_match(&self, tick_rate_us: u64) -> Result<versus::Match, String> {
+        Ok(versus::Match::new([
+            game::Game::new(tick_rate_us, self.rules.clone(), self.seeds[0], None)?,
+            game::Game::new(tick_rate_us, self.rules.clone(), self.seeds[1], None)?,
+        ]))
+    }
+
+    fn opponent(&self) -> usize {
+        1 - self.player
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.tick
+    }
+
+    // Queues a local action, it runs input_delay ticks after the next tick.
+    pub fn queue_action(&mut self, a: actions::Action) {
+        self.queued_actions.push(a)
+    }
+
+    fn receive(&mut self) -> Result<(), String> {
+        while let Some(msg) = self.connection.recv()? {
+            let opponent = self.opponent();
+            match msg {
+                Message::Input { tick, actions } => {
+                    self.inputs[opponent].insert(tick, actions);
+                }
+                Message::Garbage { tick, lines } => {
+                    self.remote_garbage.insert(tick, lines);
+                }
+                Message::Hash { tick, hash } => {
+                    self.remote_hashes.insert(tick, hash);
+                }
+                _ => return Err(format!("unexpected message {:?}", msg)),
+            }
+        }
+        Ok(())
+    }
+
+    // Simulates the next tick of the match if the opponent's input for it is known.
+    // Returns whether it did, and an error when the connection is lost or the games
+    // have desynced.
+    pub fn advance(&mut self, versus: &mut versus::Match) -> Result<bool, String> {
+        if versus.is_finished() {
+            return Ok(false);
+        }
+
+        // The local input is sent once per tick, even while waiting for the opponent.
+        if self.send_tick <= self.tick + self.input_delay {
+            let actions = std::mem::take(&mut self.queued_actions);
+            self.connection.send(&Message::Input {
+                tick: self.send_tick,
+                actions: actions.clone(),
+            })?;
+            self.inputs[self.player].insert(self.send_tick, actions);
+            self.send_tick += 1;
+        }
+        self.connection.flush()?;
+
+        self.receive()?;
+
+        // Nobody has input for the ticks before the first input delay has passed.
+        let opponent = self.opponent();
+        if self.tick >= self.input_delay && !self.inputs[opponent].contains_key(&self.tick) {
+            return Ok(false);
+        }
+
+        for player in 0..2 {
+            for a in self.inputs[player].remove(&self.tick).unwrap_or_default() {
+                let _ = versus.games[player].queue_action(a);
+            }
+        }
+        versus.tick();
+
+        if versus.sent[self.player] > 0 {
+            self.connection.send(&Message::Garbage {
+                tick: self.tick,
+                lines: versus.sent[self.player],
+            })?;
+        }
+        if versus.sent[opponent] > 0 {
+            self.local_garbage.insert(self.tick, versus.sent[opponent]);
+        }
+
+        if (self.tick + 1).is_multiple_of(HASH_INTERVAL) || versus.is_finished() {
+            let hash = versus.games[0].state_hash() ^ versus.games[1].state_hash().rotate_left(1);
+            self.connection.send(&Message::Hash {
+                tick: self.tick,
+                hash,
+            })?;
+            self.local_hashes.insert(self.tick, hash);
+        }
+
+        self.tick += 1;
+        self.check_sync()?;
+
+        Ok(true)
+    }
+
+    // Compares the hashes (and garbage sent up to them) both peers have for the same
+    // tick. The opponent sends its garbage before the hash, so it has all of it by then.
+    fn check_sync(&mut self) -> Result<(), String> {
+        while let Some((&tick, &local_hash)) = self.local_hashes.first_key_value() {
+            let Some(remote_hash) = self.remote_hashes.remove(&tick) else {
+                break;
+            };
+            self.local_hashes.remove(&tick);
+
+            let after = tick + 1;
+            let remote_garbage = self.remote_garbage.split_off(&after);
+            let local_garbage = self.local_garbage.split_off(&after);
+            let remote_garbage = std::mem::replace(&mut self.remote_garbage, remote_garbage);
+            let local_garbage = std::mem::replace(&mut self.local_garbage, local_garbage);
+
+            if remote_hash != local_hash || remote_garbage != local_garbage {
+                return Err(format!("desync detected at tick {}", tick));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetris::attack;
+    use crate::tetris::recordings;
+
+    #[test]
+    fn test_lockstep_over_loopback() {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || serve(listener));
+
+        let mut rules = rules::Rules::new();
+        rules.attack_table(attack::AttackTable::Custom(vec![1, 2, 3, 4]));
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+
+        let (addr_2, rules_2) = (addr.clone(), rules.clone());
+        let second = thread::spawn(move || connect(&addr_2, rules_2, [3, 4], 4).unwrap());
+        let mut a = connect(&addr, rules, [1, 2], 4).unwrap();
+        let mut b = second.join().unwrap();
+        let mut match_a = a.new_match(4_188).unwrap();
+        let mut match_b = b.new_match(4_188).unwrap();
+        assert_eq!(a.player + b.player, 1);
+
+        // Only the first player drops pieces, the second player's pieces fall on their own.
+        let mut peers = [(&mut a, &mut match_a), (&mut b, &mut match_b)];
+        while peers.iter().any(|(l, _)| l.ticks() < 300) {
+            for (lockstep, versus) in peers.iter_mut() {
+                if lockstep.ticks() < 300
+                    && lockstep.advance(versus).unwrap()
+                    && lockstep.player == 0
+                    && lockstep.ticks().is_multiple_of(60)
+                {
+                    lockstep.queue_action(actions::Action::Drop);
+                }
+            }
+        }
+        for player in 0..2 {
+            assert_eq!(
+                match_a.games[player].state_hash(),
+                match_b.games[player].state_hash()
+            );
+        }
+        let drops = match_b.games[0]
+            .recording
+            .events
+            .iter()
+            .filter(|ev| ev.kind == recordings::EventKind::Action(actions::Action::Drop))
+            .count();
+        assert_eq!(drops, 4);
+
+        // A game changed on one side only is detected at the next hash.
+        match_b.games[0].score_points += 1;
+        let mut peers = [(&mut a, &mut match_a), (&mut b, &mut match_b)];
+        let err = 'desync: loop {
+            for (lockstep, versus) in peers.iter_mut() {
+                if let Err(e) = lockstep.advance(versus) {
+                    break 'desync e;
+                }
+            }
+        };
+        assert!(err.contains("desync"));
+    }
+
+    #[test]
+    fn test_clients_that_left_while_waiting_are_not_paired() {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || serve(listener));
+
+        let rules = rules::Rules::new();
+        let mut gone = Connection::new(net::TcpStream::connect(&addr).unwrap()).unwrap();
+        gone.send(&Message::Hello {
+            version: PROTOCOL_VERSION,
+            rules: rules.clone(),
+            seeds: [9, 9],
+            input_delay: 4,
+        })
+        .unwrap();
+        drop(gone);
+        // Give the relay time to read every hello before the next client connects.
+        thread::sleep(time::Duration::from_millis(100));
+
+        let (addr_2, rules_2) = (addr.clone(), rules.clone());
+        let second = thread::spawn(move || connect(&addr_2, rules_2, [1, 2], 4).unwrap());
+        thread::sleep(time::Duration::from_millis(100));
+        let third = connect(&addr, rules, [3, 4], 4).unwrap();
+        let second = second.join().unwrap();
+
+        assert_eq!((second.player, third.player), (0, 1));
+        assert_eq!(third.seeds, [1, 2]);
+    }
+
+    #[test]
+    fn test_silent_clients_do_not_hold_up_the_relay() {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || serve(listener));
+
+        // Connects first and never sends a hello.
+        let _silent = net::TcpStream::connect(&addr).unwrap();
+        thread::sleep(time::Duration::from_millis(100));
+
+        let rules = rules::Rules::new();
+        let (addr_2, rules_2) = (addr.clone(), rules.clone());
+        let second = thread::spawn(move || connect(&addr_2, rules_2, [1, 2], 4).unwrap());
+        let third = connect(&addr, rules, [3, 4], 4).unwrap();
+        let second = second.join().unwrap();
+
+        assert_eq!(second.player + third.player, 1);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::tetris::modes;
+use crate::tetris::recordings;
+use crate::tetris::rules;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingFile {
+    pub version: u32,
+    pub recording: recordings::Recording,
+    pub rules: rules::Rules,
+    // Also in the rules, repeated here so results can be grouped by mode without them.
+    #[serde(default = "legacy_mode")]
+    pub mode: modes::GameMode,
+    // Recordings made before seeded piece providers only have the PieceSpawned events.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub final_score: u32,
+    pub final_lines_cleared: u32,
+}
+
+// Recordings made before game modes existed were all marathon games.
+fn legacy_mode() -> modes::GameMode {
+    modes::GameMode::Marathon
+}
+
+impl RecordingFile {
+    // Loads a recording saved by any version of the game and migrates it to the current
+    // format. Version 1 recorded game over without a reason, which was always a block out.
+    pub fn from_json(json: &str) -> Result<RecordingFile, String> {
+        let mut value: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+        let version = value["version"].as_u64().unwrap_or(1);
+        if version < 2 {
+            if let Some(events) = value["recording"]["events"].as_array_mut() {
+                for ev in events.iter_mut() {
+                    if ev["kind"] == "GameOver" {
+                        ev["kind"] = serde_json::json!({ "GameOver": "BlockOut" });
+                    }
+                }
+            }
+        }
+
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    pub fn new(
+        version: u32,
+        rules: rules::Rules,
+        recording: recordings::Recording,
+        seed: Option<u64>,
+        final_score: u32,
+        final_lines_cleared: u32,
+    ) -> RecordingFile {
+        RecordingFile {
+            version,
+            recording,
+            mode: rules.mode.clone(),
+            rules,
+            seed,
+            final_score,
+            final_lines_cleared,
+        }
+    }
+}
+
+// A versus match, the recording of every player in player order. Each recording includes
+// the garbage its player received, so the players can also be replayed on their own.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MatchFile {
+    pub version: u32,
+    pub players: Vec<RecordingFile>,
+}
+
+impl MatchFile {
+    pub fn from_json(json: &str) -> Result<MatchFile, String> {
+        let value: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+        let players = value["players"]
+            .as_array()
+            .ok_or("match file has no players".to_string())?
+            .iter()
+            .map(|player| RecordingFile::from_json(&player.to_string()))
+            .collect::<Result<Vec<RecordingFile>, String>>()?;
+
+        Ok(MatchFile {
+            version: value["version"].as_u64().unwrap_or(1) as u32,
+            players,
+        })
+    }
+
+    pub fn new(version: u32, players: Vec<RecordingFile>) -> MatchFile {
+        MatchFile { version, players }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetris::game;
+
+    #[test]
+    fn test_version_1_game_over_migrates_to_block_out() {
+        let rf = RecordingFile::new(
+            1,
+            rules::Rules::new(),
+            recordings::Recording::new(),
+            None,
+            0,
+            0,
+        );
+        let mut value = serde_json::to_value(&rf).unwrap();
+        value["recording"]["events"] = serde_json::json!([{ "kind": "GameOver", "at": 10 }]);
+        // Recordings used to repeat the garbage seed, it is derived from the seed.
+        value["garbage_seed"] = serde_json::json!(12);
+
+        let migrated = RecordingFile::from_json(&value.to_string()).unwrap();
+        assert_eq!(
+            migrated.recording.events[0].kind,
+            recordings::EventKind::GameOver(game::GameOverReason::BlockOut)
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::recording_file;
+use crate::tetris;
+use crate::tetris::game;
+use crate::tetris::tetrominos;
+
+use serde::{Deserialize, Serialize};
+
+pub struct Replay {
+    pub recording: tetris::recordings::Recording,
+}
+
+impl Replay {
+    // Queues the recorded actions for the tick after `ticks`, starting at event index
+    // `from`. Actions are recorded on the tick they ran, so every action for the next tick
+    // is queued (in recorded order) before it is simulated. Versus garbage and wins are
+    // recorded after the tick they happened on and are played back the same way. Returns
+    // the index to continue from on the next tick.
+    pub fn queue_actions(&self, gm: &mut game::Game, from: usize, ticks: usize) -> usize {
+        let mut idx = from;
+        while let Some(ev) = self.recording.events.get(idx) {
+            match ev.kind {
+                tetris::recordings::EventKind::Action(a) => {
+                    if ev.at > ticks + 1 {
+                        break;
+                    }
+                    let _ = gm.queue_action(a);
+                }
+                tetris::recordings::EventKind::GarbageReceived(lines) => {
+                    if ev.at > ticks {
+                        break;
+                    }
+                    gm.receive_garbage(lines);
+                }
+                tetris::recordings::EventKind::Finished => {
+                    if ev.at > ticks {
+                        break;
+                    }
+                    gm.end_in_victory();
+                }
+                _ => (),
+            }
+            idx += 1;
+        }
+        idx
+    }
+}
+
+// Creates the game a recording was made with, ready to have its actions played back.
+pub fn load_game(
+    tick_rate_us: u64,
+    recording_file: recording_file::RecordingFile,
+) -> Result<(game::Game, Replay), String> {
+    let replay = Replay {
+        recording: recording_file.recording,
+    };
+
+    // Seeded recordings regenerate the same pieces from the seed, older recordings
+    // feed the recorded pieces back into the game instead.
+    let replay_game = match recording_file.seed {
+        Some(seed) => game::Game::new(tick_rate_us, recording_file.rules, seed, None)?,
+        None => {
+            let replay_pieces = ReplayPieces::new(&replay);
+            game::Game::new(
+                tick_rate_us,
+                recording_file.rules,
+                0,
+                Some(Box::new(replay_pieces)),
+            )?
+        }
+    };
+
+    Ok((replay_game, replay))
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReplayPieces {
+    pieces: Vec<tetrominos::Kind>,
+    idx: usize,
+}
+
+impl ReplayPieces {
+    pub fn new(replay: &Replay) -> ReplayPieces {
+        let piece_events = replay
+            .recording
+            .events
+            .iter()
+            .filter(|ev| matches!(ev.kind, tetris::recordings::EventKind::PieceSpawned(_)));
+        let pieces = piece_events
+            .map(|ev| {
+                if let tetris::recordings::EventKind::PieceSpawned(k) = ev.kind {
+                    k
+                } else {
+                    panic!("BAD")
+                }
+            })
+            .collect();
+        ReplayPieces { pieces, idx: 0 }
+    }
+}
+
+#[typetag::serde]
+impl game::PieceProvider for ReplayPieces {
+    fn next(&mut self) -> Result<tetrominos::Kind, String> {
+        if self.idx >= self.pieces.len() {
+            return Err("NO PIECES LEFT IN THE REPLAY".to_string());
+        }
+
+        let p = self.pieces[self.idx];
+
+        self.idx += 1;
+
+        Ok(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetris::actions::Action;
+    use crate::tetris::rules;
+
+    #[test]
+    fn test_replays_run_every_action_on_the_tick_it_was_recorded() {
+        let mut rules = rules::Rules::new();
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+        let mut gm = game::Game::new(4_188, rules.clone(), 7, None).unwrap();
+        gm.drain_events();
+
+        // The actions queued after a hard drop move the next piece on the same tick.
+        let inputs = [
+            (10, vec![Action::Drop, Action::MoveLeft]),
+            (12, vec![Action::MoveLeft]),
+            (13, vec![Action::MoveLeft]),
+            (40, vec![Action::Rotate, Action::Drop, Action::MoveRight]),
+            (42, vec![Action::Drop]),
+        ];
+        let mut live_events = vec![];
+        for tick in 0..200 {
+            for (_, actions) in inputs.iter().filter(|(at, _)| *at == tick) {
+                for a in actions {
+                    let _ = gm.queue_action(*a);
+                }
+            }
+            gm.tick();
+            live_events.extend(gm.drain_events());
+        }
+        // The move queued on tick 13 is still on cooldown from the one run on tick 13.
+        let actions_at: Vec<usize> = gm
+            .recording
+            .events
+            .iter()
+            .filter(|ev| matches!(ev.kind, tetris::recordings::EventKind::Action(_)))
+            .map(|ev| ev.at)
+            .collect();
+        assert_eq!(actions_at, vec![11, 11, 13, 41, 41, 41, 43]);
+
+        let recording_file = recording_file::RecordingFile::new(
+            2,
+            rules,
+            gm.recording.clone(),
+            Some(7),
+            gm.score_points(),
+            gm.score_lines_cleared(),
+        );
+        let (mut replay_game, replay) = load_game(4_188, recording_file).unwrap();
+        replay_game.drain_events();
+        let mut replay_events = vec![];
+        let (mut idx, mut ticks) = (0, 0);
+        while ticks < 200 {
+            idx = replay.queue_actions(&mut replay_game, idx, ticks);
+            ticks = replay_game.tick();
+            replay_events.extend(replay_game.drain_events());
+        }
+
+        assert_eq!(replay_game.recording.events, gm.recording.events);
+        assert_eq!(replay_events, live_events);
+    }
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Eq, Hash, Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[repr(u8)]
+pub enum Action {
+    Drop,
+    Hold,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Rotate,
+    Rotate180,
+    RotateCcw,
+}
+
+impl Action {
+    // All rotation directions share a single cooldown so that mixing clockwise,
+    // counter-clockwise and 180 rotations can't spin a piece faster than one key would.
+    pub fn cooldown_group(self) -> Action {
+        match self {
+            Action::Rotate180 | Action::RotateCcw => Action::Rotate,
+            _ => self,
+        }
+    }
+}
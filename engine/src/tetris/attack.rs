@@ -0,0 +1,81 @@
+use crate::tetris::scoring;
+
+use serde::{Deserialize, Serialize};
+
+// Extra lines sent for the 2nd, 3rd, ... consecutive line clear, the last entry is used
+// for every combo after it.
+const GUIDELINE_COMBO_BONUS: [u32; 11] = [0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+const GUIDELINE_PERFECT_CLEAR: u32 = 10;
+
+// How many garbage lines a line clear sends to the opponent in versus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AttackTable {
+    // 0/1/2/4 for singles to tetrises, 2/4/6 for t-spins, with back-to-back, combo and
+    // perfect clear bonuses.
+    Guideline,
+    // Lines sent for clearing 1, 2, 3 and 4 lines, without any bonuses.
+    Custom(Vec<u32>),
+}
+
+impl AttackTable {
+    pub fn lines_sent(&self, clear: &scoring::ClearEvent) -> u32 {
+        if clear.lines == 0 {
+            return 0;
+        }
+
+        match self {
+            AttackTable::Guideline => {
+                let base = match (clear.tspin, clear.lines) {
+                    (scoring::TSpin::Full, lines) => 2 * lines as u32,
+                    (scoring::TSpin::Mini, lines) => lines as u32 - 1,
+                    (scoring::TSpin::None, 4) => 4,
+                    (scoring::TSpin::None, lines) => lines as u32 - 1,
+                };
+                let combo = (clear.combo.max(1) - 1) as usize;
+                let combo_bonus = GUIDELINE_COMBO_BONUS[combo.min(GUIDELINE_COMBO_BONUS.len() - 1)];
+                let perfect_clear_bonus = if clear.perfect_clear {
+                    GUIDELINE_PERFECT_CLEAR
+                } else {
+                    0
+                };
+
+                base + clear.back_to_back as u32 + combo_bonus + perfect_clear_bonus
+            }
+            AttackTable::Custom(table) => table
+                .get(clear.lines as usize - 1)
+                .or(table.last())
+                .copied()
+                .unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guideline_attack() {
+        let mut clear = scoring::ClearEvent {
+            level: 1,
+            lines: 4,
+            tspin: scoring::TSpin::None,
+            back_to_back: false,
+            combo: 1,
+            perfect_clear: false,
+        };
+        assert_eq!(AttackTable::Guideline.lines_sent(&clear), 4);
+
+        clear.lines = 2;
+        clear.tspin = scoring::TSpin::Full;
+        clear.back_to_back = true;
+        clear.combo = 3;
+        assert_eq!(AttackTable::Guideline.lines_sent(&clear), 4 + 1 + 1);
+
+        clear.lines = 1;
+        clear.tspin = scoring::TSpin::None;
+        clear.back_to_back = false;
+        clear.combo = 1;
+        assert_eq!(AttackTable::Guideline.lines_sent(&clear), 0);
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections;
+
+use crate::tetris::game;
+use crate::tetris::scoring;
+use crate::tetris::tetrominos;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum EventKind {
+    PieceSpawned(tetrominos::Kind),
+    // Moved left, right or down (by gravity, soft or hard drop) to x, y.
+    PieceMoved {
+        x: u16,
+        y: u16,
+    },
+    // The kick is the index into the rotation system kick table, 0 means no kick.
+    PieceRotated {
+        rotation: u8,
+        kick: u8,
+    },
+    PieceLocked {
+        kind: tetrominos::Kind,
+        x: u16,
+        y: u16,
+        rotation: u8,
+    },
+    // Matrix row indices of the cleared rows.
+    LinesCleared {
+        rows: Vec<usize>,
+        tspin: scoring::TSpin,
+    },
+    LevelUp(u8),
+    Hold(tetrominos::Kind),
+    GameOver(game::GameOverReason),
+    Finished,
+    // Garbage lines rose into the well from the bottom.
+    GarbageRisen(u32),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Event {
+    pub kind: EventKind,
+    pub at: usize,
+}
+
+// Events buffered by the game until a consumer drains them. Only the most recent
+// events are kept so a game nobody drains does not grow without bounds.
+pub struct EventBuffer {
+    events: collections::VecDeque<Event>,
+}
+
+const MAX_BUFFERED_EVENTS: usize = 1024;
+
+impl EventBuffer {
+    pub fn new() -> EventBuffer {
+        EventBuffer {
+            events: collections::VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, at: usize, kind: EventKind) {
+        if self.events.len() >= MAX_BUFFERED_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(Event { kind, at })
+    }
+
+    pub fn drain(&mut self) -> Vec<Event> {
+        self.events.drain(..).collect()
+    }
+}
+
+impl Default for EventBuffer {
+    fn default() -> EventBuffer {
+        EventBuffer::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a_full_buffer_drops_the_oldest_events() {
+        let mut buffer = EventBuffer::new();
+        for at in 0..MAX_BUFFERED_EVENTS + 10 {
+            buffer.push(at, EventKind::Finished);
+        }
+
+        let events = buffer.drain();
+        assert_eq!(events.len(), MAX_BUFFERED_EVENTS);
+        assert_eq!(events[0].at, 10);
+        assert!(buffer.drain().is_empty());
+    }
+}
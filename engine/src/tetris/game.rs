@@ -0,0 +1,1616 @@
+use std::collections;
+use std::fmt;
+use std::time;
+
+use crate::tetris::actions;
+use crate::tetris::events;
+use crate::tetris::garbage;
+use crate::tetris::gravity;
+use crate::tetris::modes;
+use crate::tetris::playfield;
+use crate::tetris::polyominos;
+use crate::tetris::randomizers;
+use crate::tetris::recordings;
+use crate::tetris::rotation;
+use crate::tetris::rules;
+use crate::tetris::scoring;
+use crate::tetris::tetrominos;
+
+#[cfg(feature = "thread-rng")]
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use typetag;
+
+#[derive(Serialize, Deserialize)]
+pub struct Piece {
+    pub tetromino: tetrominos::Kind,
+    pub x: u16,
+    pub y: u16,
+    // Gravity accumulated since the piece last moved down, see gravity::ONE_G.
+    creep: u32,
+    rotation: u8,
+    busy_locking: bool,
+    remaining_lock_frames: u32,
+    // Lock delay resets used since the piece reached its lowest row, see rules::LockDown.
+    // Pieces saved before lock down modes existed start counting where they are.
+    #[serde(default)]
+    lock_resets: u8,
+    #[serde(default)]
+    lowest_row: u16,
+    // The kick used by the last successful rotation, cleared again when the piece moves.
+    rotation_kick: Option<u8>,
+}
+
+impl Piece {
+    fn new(k: tetrominos::Kind) -> Piece {
+        Piece {
+            tetromino: k,
+            x: 4,
+            y: 0,
+            creep: 0,
+            rotation: 0,
+            busy_locking: false,
+            remaining_lock_frames: 0,
+            lock_resets: 0,
+            lowest_row: 0,
+            rotation_kick: None,
+        }
+    }
+}
+
+#[typetag::serde(tag = "type")]
+pub trait PieceProvider {
+    fn next(&mut self) -> Result<tetrominos::Kind, String>;
+}
+
+const MAX_QUEUED_ACTIONS: usize = 8;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub enum State {
+    Init,
+    Playing,
+    // Full rows have been cleared and the well collapses when the remaining ticks run out.
+    ClearingLines(u32),
+    // Entry delay (ARE), the next piece spawns when the remaining ticks run out.
+    Spawning(u32),
+    GameOver,
+    // The game mode goal was reached.
+    Victory,
+}
+
+// The names are the guideline terms, hence the shared postfix.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameOverReason {
+    // The next piece collides with the stack as it spawns.
+    BlockOut,
+    // A piece locked completely above the visible well.
+    LockOut,
+    // Garbage pushed the stack out of the top of the well.
+    TopOut,
+}
+
+impl fmt::Display for GameOverReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameOverReason::BlockOut => write!(f, "Block Out"),
+            GameOverReason::LockOut => write!(f, "Lock Out"),
+            GameOverReason::TopOut => write!(f, "Top Out"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Game {
+    pub rules: rules::Rules,
+    state: State,
+    game_over_reason: Option<GameOverReason>,
+    tick_rate_us: u64,
+    ticks: usize,
+    pub level: u8,
+    // Rows per tick in 1/65536ths, see gravity::ONE_G. Games saved before gravity curves
+    // existed get it from their level in Game::from_json.
+    #[serde(default)]
+    pub gravity: u32,
+    // The seed used for the piece provider, playing the same seed gives the same pieces.
+    // Games saved before seeds existed had none, they load with seed 0.
+    #[serde(default)]
+    pub seed: u64,
+    // Seeded from the game seed, see garbage::seed_for.
+    #[serde(default = "legacy_garbage")]
+    garbage: garbage::Generator,
+    // Versus garbage sent by line clears that the opponent has not taken yet.
+    #[serde(default)]
+    outgoing_garbage: u32,
+    // Versus garbage received, it rises into the well when a piece locks without clearing.
+    #[serde(default)]
+    incoming_garbage: u32,
+    pub play_field: playfield::PlayField,
+    // The rows cleared by the last piece lock, until the well has collapsed.
+    #[serde(default)]
+    pub clearing_rows: Vec<usize>,
+    // Games saved before the next queue existed load with an empty one, it is filled
+    // again when the next piece spawns.
+    #[serde(default)]
+    pub next_queue: collections::VecDeque<tetrominos::Kind>,
+    pub hold_piece: Option<tetrominos::Kind>,
+    #[serde(default = "legacy_can_hold")]
+    can_hold: bool,
+    piece_provider: Box<dyn PieceProvider>,
+    // The pieces of a custom piece set, see polyominos::PieceSet.
+    #[serde(default)]
+    polyominos: Vec<polyominos::Polyomino>,
+    pub piece: Piece,
+    scoring_system: Box<dyn scoring::System>,
+    pub score_points: u32,
+    pub score_lines_cleared: u32,
+    level_lines_cleared: u32,
+    // Consecutive piece locks that cleared lines, 0 when the last lock cleared nothing.
+    #[serde(default)]
+    pub combo: u32,
+    // Consecutive difficult line clears, every clear after the first one is back-to-back.
+    #[serde(default)]
+    pub back_to_back: u32,
+    #[serde(default)]
+    pub perfect_clears: u32,
+    // Actions run in the order they were queued, all on the next tick. A hard drop locks
+    // the piece at once, the actions queued after it move the next piece.
+    #[serde(default)]
+    action_queue: collections::VecDeque<actions::Action>,
+    last_action_at: usize,
+    actions_last_used_at: collections::HashMap<actions::Action, usize>,
+
+    pub recording: recordings::Recording,
+    // Not saved, consumers drain the events as the game runs.
+    #[serde(skip)]
+    events: events::EventBuffer,
+}
+
+// Games saved before hold existed had nothing held, so holding is allowed.
+fn legacy_can_hold() -> bool {
+    true
+}
+
+// Games saved before garbage existed have seed 0.
+fn legacy_garbage() -> garbage::Generator {
+    garbage::Generator::new(garbage::seed_for(0))
+}
+
+// Behind the thread-rng feature, platforms without OS randomness (wasm) only replay or
+// start games from a known seed.
+#[cfg(feature = "thread-rng")]
+pub fn new_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+impl Game {
+    // Loads a game saved by any version of the game. Games saved before gravity curves
+    // existed get the gravity of their level, and the next piece of games saved before
+    // the next queue existed is the front of the queue.
+    pub fn from_json(json: &str) -> Result<Game, String> {
+        let mut value: serde_json::Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+        if value.get("next_queue").is_none() {
+            if let Some(next_piece) = value.get("next_piece").cloned() {
+                value["next_queue"] = serde_json::json!([next_piece]);
+            }
+        }
+        let legacy_gravity = value.get("gravity").is_none();
+
+        let mut game: Game = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        if legacy_gravity {
+            game.gravity =
+                gravity::for_level(&game.rules.gravity_curve, game.level, game.tick_rate_us);
+        }
+
+        Ok(game)
+    }
+
+    pub fn new(
+        tick_rate_us: u64,
+        rules: rules::Rules,
+        seed: u64,
+        piece_provider: Option<Box<dyn PieceProvider>>,
+    ) -> Result<Game, String> {
+        let play_field = playfield::PlayField::new(rules.well_height, rules.well_width)?;
+        let polyominos = rules.piece_set.polyominos()?;
+        // Pieces spawn centred in their box, which has to fit in the well.
+        let widest = polyominos.iter().map(|p| p.forms[0].size()).max();
+        if let Some(widest) = widest.filter(|widest| rules.well_width < *widest) {
+            return Err(format!(
+                "the well needs to be {} wide for the piece set",
+                widest
+            ));
+        }
+
+        let provider = match piece_provider {
+            Some(p) => p,
+            None => randomizers::new(&rules.randomizer, seed, polyominos::kinds(&polyominos)),
+        };
+
+        let ss: Box<dyn scoring::System> = match rules.scoring_system {
+            scoring::Kind::DS => Box::new(scoring::DS::new()),
+            scoring::Kind::OriginalBPS => Box::new(scoring::OriginalBPS::new()),
+            scoring::Kind::OriginalNintendo => Box::new(scoring::OriginalNintendo::new()),
+            scoring::Kind::OriginalSega => Box::new(scoring::OriginalSega::new()),
+            scoring::Kind::Guideline => Box::new(scoring::Guideline::new()),
+        };
+
+        let gravity = gravity::for_level(&rules.gravity_curve, 1, tick_rate_us);
+
+        let mut g = Game {
+            rules,
+            state: State::Init,
+            game_over_reason: None,
+            tick_rate_us: tick_rate_us,
+            ticks: 0,
+            level: 1,
+            gravity,
+            seed,
+            garbage: garbage::Generator::new(garbage::seed_for(seed)),
+            outgoing_garbage: 0,
+            incoming_garbage: 0,
+            play_field,
+            clearing_rows: vec![],
+
+            piece_provider: provider,
+            polyominos,
+            piece: Piece::new(tetrominos::Kind::Stick),
+            next_queue: collections::VecDeque::new(),
+            hold_piece: None,
+            can_hold: true,
+
+            scoring_system: ss,
+            score_points: 0,
+            score_lines_cleared: 0,
+            level_lines_cleared: 0,
+            combo: 0,
+            back_to_back: 0,
+            perfect_clears: 0,
+
+            action_queue: collections::VecDeque::new(),
+            last_action_at: 0,
+            actions_last_used_at: collections::HashMap::from([]),
+            recording: recordings::Recording::new(),
+            events: events::EventBuffer::new(),
+        };
+
+        if let modes::GameMode::Dig(rows) = g.rules.mode {
+            if rows as usize >= g.play_field.rows {
+                return Err("dig needs fewer garbage rows than the well is high".to_string());
+            }
+            for _ in 0..rows {
+                let hole = g.garbage.next_hole(g.play_field.cols);
+                g.play_field.insert_garbage_row(hole);
+            }
+        }
+
+        // Fill the next queue from the piece provider and spawn the first piece
+        // from it to replace the temp value set above.
+        g.refill_next_queue()?;
+        g.grab_next_piece()?;
+
+        g.state = State::Playing;
+
+        Ok(g)
+    }
+
+    pub fn tick(&mut self) -> usize {
+        if self.is_finished() {
+            return self.ticks;
+        }
+
+        // Timed modes end after exactly the limit's number of ticks have been simulated.
+        if self.rules.mode.is_time_up(self.ticks) {
+            self.victory();
+            return self.ticks;
+        }
+
+        self.ticks += 1;
+        //println!("SIMULATING GAME ENGINE... {:?} {:?} {:?}", t, dt, acc);
+        //
+
+        let delay_result = match self.state {
+            State::ClearingLines(remaining) if remaining > 1 => {
+                self.state = State::ClearingLines(remaining - 1);
+                Some(Ok(()))
+            }
+            State::ClearingLines(_) => {
+                self.state = State::Playing;
+                Some(self.finish_line_clear())
+            }
+            State::Spawning(remaining) if remaining > 1 => {
+                self.state = State::Spawning(remaining - 1);
+                Some(Ok(()))
+            }
+            State::Spawning(_) => {
+                self.state = State::Playing;
+                Some(self.grab_next_piece().map_err(|_| GameOverReason::BlockOut))
+            }
+            _ => None,
+        };
+        if let Some(result) = delay_result {
+            if let Err(reason) = result {
+                self.game_over(reason);
+            }
+
+            return self.ticks;
+        }
+        let mut soft_dropped = false;
+        while let Some(action) = self.action_queue.pop_front() {
+            self.recording.push_action(self.ticks, action);
+
+            match action {
+                actions::Action::MoveDown => {
+                    self.drop_one();
+                    soft_dropped = true;
+                }
+                actions::Action::MoveLeft => self.move_left(),
+                actions::Action::MoveRight => self.move_right(),
+                actions::Action::Rotate => self.rotate(),
+                actions::Action::Rotate180 => self.rotate_180(),
+                actions::Action::RotateCcw => self.rotate_ccw(),
+                actions::Action::Drop => {
+                    let rows = self.drop_fast();
+                    self.score_points += self.scoring_system.dropped(rows as u32, true);
+                    if self.rules.lock_delay_on_hard_drop {
+                        continue;
+                    }
+
+                    self.piece.busy_locking = false;
+                    soft_dropped = false;
+                    if let Err(reason) = self.lock_piece() {
+                        self.game_over(reason);
+
+                        return self.ticks;
+                    }
+                    // Actions queued for the line clear or spawn delay are dropped.
+                    if self.state != State::Playing {
+                        self.action_queue.clear();
+
+                        return self.ticks;
+                    }
+                }
+                actions::Action::Hold => {
+                    if self.hold().is_err() {
+                        self.game_over(GameOverReason::BlockOut);
+
+                        return self.ticks;
+                    }
+                }
+            }
+        }
+
+        self.piece.creep += self.gravity;
+        while self.piece.creep >= gravity::ONE_G {
+            self.piece.creep -= gravity::ONE_G;
+
+            if !self.can_fall() {
+                self.piece.creep = 0;
+                break;
+            }
+
+            self.piece.y += 1;
+            self.piece.rotation_kick = None;
+            self.reached_row();
+            self.emit_piece_moved();
+            if soft_dropped {
+                self.score_points += self.scoring_system.dropped(1, false);
+            }
+
+            // Below 1G every row takes a whole number of ticks, which keeps the legacy
+            // curve (and recordings made with it) tick exact.
+            if self.gravity < gravity::ONE_G {
+                self.piece.creep = 0;
+            }
+        }
+
+        if !self.can_fall() {
+            if self.piece.busy_locking {
+                if self.piece.remaining_lock_frames == 0 || self.lock_resets_exhausted() {
+                    self.piece.busy_locking = false;
+                    if let Err(reason) = self.lock_piece() {
+                        self.game_over(reason);
+
+                        return self.ticks;
+                    }
+                } else {
+                    self.piece.remaining_lock_frames -= 1;
+                }
+            } else {
+                self.piece.busy_locking = true;
+                self.piece.remaining_lock_frames = self.rules.lock_delay;
+            }
+        }
+
+        self.ticks
+    }
+
+    // Imprint the piece into the well, clear and score any full rows and then spawn
+    // the next piece, after the line clear and spawn delays when those are set.
+    fn lock_piece(&mut self) -> Result<(), GameOverReason> {
+        let tspin = self.detect_tspin();
+        let locked_out = self.is_above_well();
+        self.imprint_piece();
+        self.emit(events::EventKind::PieceLocked {
+            kind: self.piece.tetromino,
+            x: self.piece.x,
+            y: self.piece.y,
+            rotation: self.piece.rotation,
+        });
+        if locked_out {
+            return Err(GameOverReason::LockOut);
+        }
+
+        self.clearing_rows = self.play_field.full_rows();
+        let lines_cleared = self.play_field.clear_full_rows();
+        if lines_cleared > 0 {
+            self.emit(events::EventKind::LinesCleared {
+                rows: self.clearing_rows.clone(),
+                tspin,
+            });
+        }
+        self.level_lines_cleared += lines_cleared;
+        self.score_lines_cleared += lines_cleared;
+
+        let perfect_clear = lines_cleared > 0 && self.play_field.is_empty();
+        if lines_cleared > 0 {
+            self.combo += 1;
+        } else {
+            self.combo = 0;
+        }
+
+        let mut clear = scoring::ClearEvent {
+            level: self.level,
+            lines: lines_cleared as u8,
+            tspin,
+            back_to_back: false,
+            combo: self.combo,
+            perfect_clear,
+        };
+        if lines_cleared > 0 {
+            if clear.is_difficult() {
+                clear.back_to_back = self.back_to_back > 0;
+                self.back_to_back += 1;
+            } else {
+                self.back_to_back = 0;
+            }
+        }
+
+        if self.combo > 1 {
+            self.recording.push_combo(self.ticks, self.combo);
+        }
+        if clear.back_to_back {
+            self.recording
+                .push_back_to_back(self.ticks, self.back_to_back);
+        }
+        if perfect_clear {
+            self.perfect_clears += 1;
+            self.recording.push_perfect_clear(self.ticks);
+        }
+
+        self.score_points += self.scoring_system.lines_cleared(&clear);
+
+        // Line clears cancel incoming garbage before sending any.
+        let attack = self.rules.attack_table.lines_sent(&clear);
+        let cancelled = attack.min(self.incoming_garbage);
+        self.incoming_garbage -= cancelled;
+        self.outgoing_garbage += attack - cancelled;
+
+        if self.lines_remaining() == Some(0) {
+            self.play_field.collapse();
+            self.clearing_rows.clear();
+            self.victory();
+            return Ok(());
+        }
+
+        if self.level_lines_cleared >= self.rules.level_goal.lines_for_level(self.level) {
+            self.level = self.level.saturating_add(1);
+            self.emit(events::EventKind::LevelUp(self.level));
+            self.level_lines_cleared = 0;
+            self.gravity =
+                gravity::for_level(&self.rules.gravity_curve, self.level, self.tick_rate_us);
+        }
+
+        if lines_cleared > 0 && self.rules.line_clear_delay > 0 {
+            self.state = State::ClearingLines(self.rules.line_clear_delay);
+            return Ok(());
+        }
+
+        if lines_cleared == 0 && self.incoming_garbage > 0 {
+            self.rise_garbage()?;
+        }
+
+        self.finish_line_clear()
+    }
+
+    // All the incoming garbage rises at once, sharing a single hole column.
+    fn rise_garbage(&mut self) -> Result<(), GameOverReason> {
+        let lines = self.incoming_garbage;
+        let hole = self.garbage.next_hole(self.play_field.cols);
+        self.incoming_garbage = 0;
+        self.emit(events::EventKind::GarbageRisen(lines));
+
+        for _ in 0..lines {
+            if self.play_field.insert_garbage_row(hole) {
+                return Err(GameOverReason::TopOut);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Versus garbage from the opponent, see rise_garbage.
+    pub fn receive_garbage(&mut self, lines: u32) {
+        if lines == 0 || self.is_finished() {
+            return;
+        }
+
+        self.incoming_garbage += lines;
+        self.recording.push_garbage_received(self.ticks, lines);
+    }
+
+    // The garbage this game sent since the last call, for the opponent to receive.
+    pub fn take_attack(&mut self) -> u32 {
+        std::mem::take(&mut self.outgoing_garbage)
+    }
+
+    pub fn incoming_garbage(&self) -> u32 {
+        self.incoming_garbage
+    }
+
+    // Ends the game as won (when the versus opponent tops out).
+    pub fn end_in_victory(&mut self) {
+        if !self.is_finished() {
+            self.victory();
+        }
+    }
+
+    fn finish_line_clear(&mut self) -> Result<(), GameOverReason> {
+        self.play_field.collapse();
+        self.clearing_rows.clear();
+
+        if self.rules.spawn_delay > 0 {
+            self.state = State::Spawning(self.rules.spawn_delay);
+            return Ok(());
+        }
+
+        self.grab_next_piece().map_err(|_| GameOverReason::BlockOut)
+    }
+
+    fn game_over(&mut self, reason: GameOverReason) {
+        self.state = State::GameOver;
+        self.game_over_reason = Some(reason);
+        self.recording.gameover(self.ticks, reason);
+        self.emit(events::EventKind::GameOver(reason));
+    }
+
+    fn victory(&mut self) {
+        self.state = State::Victory;
+        self.recording.finished(self.ticks);
+        self.emit(events::EventKind::Finished);
+    }
+
+    fn emit(&mut self, kind: events::EventKind) {
+        self.events.push(self.ticks, kind);
+    }
+
+    fn emit_piece_moved(&mut self) {
+        self.emit(events::EventKind::PieceMoved {
+            x: self.piece.x,
+            y: self.piece.y,
+        });
+    }
+
+    // Everything that happened since the last call, in the order it happened.
+    pub fn drain_events(&mut self) -> Vec<events::Event> {
+        self.events.drain()
+    }
+
+    // True when none of the piece cells are inside the visible well.
+    fn is_above_well(&self) -> bool {
+        let well_y = self.play_field.well_y();
+        self.piece_form()
+            .iter()
+            .enumerate()
+            .filter(|(_, cells)| cells.contains(&1))
+            .all(|(row, _)| self.piece.y as usize + row < well_y)
+    }
+
+    // T-spins use the 3 corner rule: the last successful movement of a T piece was a
+    // rotation and at least 3 of the 4 cells diagonal to its centre are occupied.
+    // It is a mini t-spin unless both corners on the pointing side are occupied or the
+    // rotation needed the last (1, 2) kick of the SRS tables.
+    fn detect_tspin(&self) -> scoring::TSpin {
+        let Some(kick) = self.piece.rotation_kick else {
+            return scoring::TSpin::None;
+        };
+        if self.piece.tetromino != tetrominos::Kind::Pyramid {
+            return scoring::TSpin::None;
+        }
+
+        let form = self.piece_form();
+        let size = form.size() as i32;
+        let form_cell = |row: i32, col: i32| -> bool {
+            (0..size).contains(&row)
+                && (0..size).contains(&col)
+                && form[row as usize][col as usize] == 1
+        };
+        let neighbours = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+        // The centre of the T is the only cell with 3 neighbours, the missing
+        // neighbour is on the flat side opposite to where the T is pointing.
+        let mut centre = None;
+        for row in 0..size {
+            for col in 0..size {
+                if !form_cell(row, col) {
+                    continue;
+                }
+                let missing: Vec<&(i32, i32)> = neighbours
+                    .iter()
+                    .filter(|(dy, dx)| !form_cell(row + dy, col + dx))
+                    .collect();
+                if missing.len() == 1 {
+                    centre = Some((row, col, -missing[0].0, -missing[0].1));
+                }
+            }
+        }
+        let Some((row, col, point_y, point_x)) = centre else {
+            return scoring::TSpin::None;
+        };
+
+        let centre_y = self.piece.y as i32 + row;
+        let centre_x = self.piece.x as i32 + col;
+        let occupied = |y: i32, x: i32| -> bool {
+            if y < 0 || x < 0 {
+                return true;
+            }
+            !matches!(
+                self.play_field
+                    .matrix
+                    .get(y as usize)
+                    .and_then(|r| r.get(x as usize)),
+                Some(playfield::Location::Empty)
+            )
+        };
+
+        let corners = [(-1, -1), (-1, 1), (1, -1), (1, 1)]
+            .iter()
+            .filter(|(dy, dx)| occupied(centre_y + dy, centre_x + dx))
+            .count();
+        if corners < 3 {
+            return scoring::TSpin::None;
+        }
+
+        let front_corners = occupied(centre_y + point_y + point_x, centre_x + point_x + point_y)
+            && occupied(centre_y + point_y - point_x, centre_x + point_x - point_y);
+        // Under SRS a T that needed the last kick (the TST kick) is a full T-spin, the
+        // other rotation systems number their kicks differently.
+        let last_srs_kick =
+            matches!(self.rules.rotation_system, rotation::Kind::SuperRotation) && kick == 4;
+        if front_corners || last_srs_kick {
+            scoring::TSpin::Full
+        } else {
+            scoring::TSpin::Mini
+        }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    // There is no piece in play during the line clear and spawn delays.
+    pub fn is_piece_active(&self) -> bool {
+        self.state == State::Playing
+    }
+
+    pub fn game_over_reason(&self) -> Option<GameOverReason> {
+        self.game_over_reason
+    }
+
+    pub fn is_gameover(&self) -> bool {
+        self.state == State::GameOver
+    }
+
+    pub fn is_victory(&self) -> bool {
+        self.state == State::Victory
+    }
+
+    // Game over or won, either way the game has ended.
+    pub fn is_finished(&self) -> bool {
+        self.is_gameover() || self.is_victory()
+    }
+
+    // Lines still needed to win, for modes with a line goal.
+    pub fn lines_remaining(&self) -> Option<u32> {
+        match self.rules.mode {
+            modes::GameMode::Dig(_) => Some(self.play_field.garbage_rows() as u32),
+            _ => self.rules.mode.lines_remaining(self.score_lines_cleared),
+        }
+    }
+
+    // Time left to play, for modes with a time limit.
+    pub fn time_remaining(&self) -> Option<time::Duration> {
+        self.rules
+            .mode
+            .ticks_remaining(self.ticks)
+            .map(|ticks| time::Duration::from_micros(ticks as u64 * self.tick_rate_us))
+    }
+
+    pub fn time(&self) -> time::Duration {
+        time::Duration::from_micros(self.ticks as u64 * self.tick_rate_us)
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    pub fn score_points(&self) -> u32 {
+        self.score_points
+    }
+
+    pub fn score_lines_cleared(&self) -> u32 {
+        self.score_lines_cleared
+    }
+
+    // A hash of the simulation state that must match between lockstep peers, see netplay.
+    // It is FNV-1a over the JSON of the state, std's hashers are not stable across builds.
+    pub fn state_hash(&self) -> u64 {
+        let state = (
+            self.ticks,
+            &self.state,
+            &self.play_field,
+            &self.piece,
+            &self.next_queue,
+            self.hold_piece,
+            self.score_points,
+            self.score_lines_cleared,
+            self.incoming_garbage,
+            self.outgoing_garbage,
+        );
+        let bytes = serde_json::to_vec(&state).unwrap_or_default();
+
+        bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    // Cooldowns are checked against the next tick, the tick the action is queued for.
+    // A shared cooldown lets one action through per tick at most, with Each cooldowns
+    // actions from different cooldown groups (a rotate and a move) can share a tick.
+    pub fn queue_action(&mut self, a: actions::Action) -> Result<(), String> {
+        if self.state != State::Playing {
+            return Err("Can't queue action while game is not ready".to_string());
+        }
+
+        if self.action_queue.len() >= MAX_QUEUED_ACTIONS {
+            return Err("Action queue is full".to_string());
+        }
+
+        match self.rules.action_cooldown {
+            rules::ActionCooldown::Shared(ticks) => {
+                if self.last_action_at + ticks as usize >= self.ticks {
+                    return Err("Can't queue action while game IS ON COOLDOWN".to_string());
+                }
+            }
+            rules::ActionCooldown::Each(ticks) => {
+                let action_last_used_at = self
+                    .actions_last_used_at
+                    .entry(a.cooldown_group())
+                    .or_insert_with(|| 0);
+                if *action_last_used_at + ticks as usize >= self.ticks {
+                    return Err("Can't queue action while {} IS ON COOLDOWN".to_string());
+                }
+            }
+        };
+
+        let next_tick = self.ticks + 1;
+        self.last_action_at = next_tick;
+        self.actions_last_used_at
+            .insert(a.cooldown_group(), next_tick);
+        self.action_queue.push_back(a);
+
+        Ok(())
+    }
+
+    // Custom pieces have the same forms in every rotation system.
+    pub fn forms(&self, k: tetrominos::Kind) -> &[tetrominos::Form; 4] {
+        match k {
+            tetrominos::Kind::Custom(idx) => &self.polyominos[idx as usize].forms,
+            _ => rotation::from_kind(&self.rules.rotation_system)
+                .forms(k)
+                .expect("rotation systems have forms for every tetromino"),
+        }
+    }
+
+    // The name and colour of a custom piece.
+    pub fn polyomino(&self, k: tetrominos::Kind) -> Option<&polyominos::Polyomino> {
+        match k {
+            tetrominos::Kind::Custom(idx) => self.polyominos.get(idx as usize),
+            _ => None,
+        }
+    }
+
+    pub fn piece_form(&self) -> &tetrominos::Form {
+        &self.forms(self.piece.tetromino)[self.piece.rotation as usize]
+    }
+
+    pub fn rotate(&mut self) {
+        self.rotate_to((self.piece.rotation + 1) % 4)
+    }
+
+    pub fn rotate_ccw(&mut self) {
+        self.rotate_to((self.piece.rotation + 3) % 4)
+    }
+
+    pub fn rotate_180(&mut self) {
+        self.rotate_to((self.piece.rotation + 2) % 4)
+    }
+
+    fn rotate_to(&mut self, next_rotation: u8) {
+        let system = rotation::from_kind(&self.rules.rotation_system);
+        let next_form = &self.forms(self.piece.tetromino)[next_rotation as usize];
+
+        let mut kicks = system.kicks(self.piece.tetromino, self.piece.rotation, next_rotation);
+        if !self.rules.wall_kicks {
+            kicks = &kicks[..1];
+        }
+
+        for (idx, (kick_x, kick_y)) in kicks.iter().enumerate() {
+            if idx == 1
+                && !system.can_kick(
+                    &self.play_field,
+                    self.piece.tetromino,
+                    self.piece.y as usize,
+                    self.piece.x as usize,
+                    next_form,
+                )
+            {
+                return;
+            }
+
+            let x = self.piece.x as i32 + *kick_x as i32;
+            let y = self.piece.y as i32 + *kick_y as i32;
+            if x < 0 || y < 0 {
+                continue;
+            }
+
+            if !self
+                .play_field
+                .has_collission(y as usize, x as usize, next_form)
+            {
+                self.piece.rotation = next_rotation;
+                self.piece.x = x as u16;
+                self.piece.y = y as u16;
+                self.piece.rotation_kick = Some(idx as u8);
+                self.emit(events::EventKind::PieceRotated {
+                    rotation: next_rotation,
+                    kick: idx as u8,
+                });
+                self.reached_row();
+                self.reset_remaining_lock_frames(true);
+                return;
+            }
+        }
+    }
+
+    fn reset_remaining_lock_frames(&mut self, rotated: bool) {
+        if !self.piece.busy_locking {
+            return;
+        }
+
+        let reset = match self.rules.lock_down {
+            rules::LockDown::MoveReset => !rotated,
+            rules::LockDown::Infinite => true,
+            rules::LockDown::Extended(limit) => {
+                let reset = self.piece.lock_resets < limit;
+                if reset {
+                    self.piece.lock_resets += 1;
+                }
+                reset
+            }
+            rules::LockDown::StepReset => false,
+        };
+        if reset {
+            self.piece.remaining_lock_frames = self.rules.lock_delay;
+        }
+    }
+
+    fn lock_resets_exhausted(&self) -> bool {
+        match self.rules.lock_down {
+            rules::LockDown::Extended(limit) => self.piece.lock_resets >= limit,
+            _ => false,
+        }
+    }
+
+    // Keeps track of the lowest row the piece reached, a new lowest row starts the lock
+    // delay (and the resets allowed) over for the step and extended lock down modes.
+    fn reached_row(&mut self) {
+        if self.piece.y <= self.piece.lowest_row {
+            return;
+        }
+        self.piece.lowest_row = self.piece.y;
+
+        if matches!(
+            self.rules.lock_down,
+            rules::LockDown::Extended(_) | rules::LockDown::StepReset
+        ) {
+            self.piece.lock_resets = 0;
+            self.piece.busy_locking = false;
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if self.piece.x == 0 {
+            return;
+        }
+
+        if !self.play_field.has_collission(
+            self.piece.y as usize,
+            self.piece.x as usize - 1,
+            self.piece_form(),
+        ) {
+            self.piece.x -= 1;
+            self.piece.rotation_kick = None;
+            self.emit_piece_moved();
+            self.reset_remaining_lock_frames(false);
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if !self.play_field.has_collission(
+            self.piece.y as usize,
+            self.piece.x as usize + 1,
+            self.piece_form(),
+        ) {
+            self.piece.x += 1;
+            self.piece.rotation_kick = None;
+            self.emit_piece_moved();
+            self.reset_remaining_lock_frames(false);
+        }
+    }
+
+    fn can_fall(&mut self) -> bool {
+        return !self.play_field.has_collission(
+            self.piece.y as usize + 1,
+            self.piece.x as usize,
+            self.piece_form(),
+        );
+    }
+
+    pub fn drop_distance(&self) -> usize {
+        let mut distance = 0;
+
+        while !self.play_field.has_collission(
+            self.piece.y as usize + distance,
+            self.piece.x as usize,
+            self.piece_form(),
+        ) {
+            distance += 1
+        }
+
+        distance
+    }
+
+    // Tops up the gravity so the piece moves down at least one row on this tick.
+    pub fn drop_one(&mut self) {
+        self.piece.creep = self
+            .piece
+            .creep
+            .max(gravity::ONE_G - self.gravity.min(gravity::ONE_G));
+    }
+
+    // Returns the number of rows the piece dropped.
+    pub fn drop_fast(&mut self) -> usize {
+        let mut rows = 0;
+        while self.can_fall() {
+            self.piece.y += 1;
+            self.piece.rotation_kick = None;
+            rows += 1;
+        }
+        self.reached_row();
+        if rows > 0 {
+            self.emit_piece_moved();
+        }
+
+        rows
+    }
+
+    pub fn can_hold(&self) -> bool {
+        self.can_hold
+    }
+
+    // Swap the active piece with the one in the hold slot. When the hold slot is
+    // still empty the next piece is pulled in instead. Only allowed once per spawn.
+    pub fn hold(&mut self) -> Result<(), String> {
+        if !self.can_hold {
+            return Ok(());
+        }
+
+        let current = self.piece.tetromino;
+        match self.hold_piece {
+            Some(held) => self.spawn_piece(held)?,
+            None => self.grab_next_piece()?,
+        }
+
+        self.hold_piece = Some(current);
+        self.can_hold = false;
+        self.emit(events::EventKind::Hold(current));
+
+        Ok(())
+    }
+
+    // Pieces are recorded as they come out of the piece provider (not when they spawn)
+    // so that a replay can feed them back in the same order.
+    fn refill_next_queue(&mut self) -> Result<(), String> {
+        while self.next_queue.len() < self.rules.next_queue_size.max(1) as usize {
+            let next_piece = self.piece_provider.next()?;
+            self.recording.push_piece(self.ticks, next_piece);
+            self.next_queue.push_back(next_piece);
+        }
+
+        Ok(())
+    }
+
+    pub fn grab_next_piece(&mut self) -> Result<(), String> {
+        self.refill_next_queue()?;
+        let Some(kind) = self.next_queue.pop_front() else {
+            return Err("NEXT QUEUE IS EMPTY".to_string());
+        };
+        self.refill_next_queue()?;
+        self.can_hold = true;
+
+        self.spawn_piece(kind)
+    }
+
+    fn spawn_piece(&mut self, k: tetrominos::Kind) -> Result<(), String> {
+        self.piece.tetromino = k;
+        self.piece.rotation = 0;
+        let size = self.piece_form().size();
+        self.piece.x = (self.play_field.well_x() + (self.play_field.cols / 2) - size / 2) as u16;
+        self.piece.y = 2;
+        self.piece.creep = 0;
+        self.piece.busy_locking = false;
+        self.piece.remaining_lock_frames = 0;
+        self.piece.lock_resets = 0;
+        self.piece.lowest_row = self.piece.y;
+        self.piece.rotation_kick = None;
+
+        if self.play_field.has_collission(
+            self.piece.y as usize,
+            self.piece.x as usize,
+            self.piece_form(),
+        ) {
+            return Err("GAME OVER - NOT ENOUGH SPACE IN WELL TO PLACE NEXT PIECE xD".to_string());
+        }
+        self.emit(events::EventKind::PieceSpawned(k));
+
+        Ok(())
+    }
+
+    fn imprint_piece(&mut self) {
+        let row_offset = self.piece.y as usize;
+        let col_offset = self.piece.x as usize;
+
+        let shape = *self.piece_form();
+
+        for row in 0..shape.size() {
+            for col in 0..shape.size() {
+                if shape[row][col] == 1 {
+                    self.play_field.matrix[row + row_offset][col + col_offset] =
+                        playfield::Location::Filled(self.piece.tetromino);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_actions_from_different_cooldown_groups_share_a_tick() {
+        let mut rules = rules::Rules::new();
+        rules.action_cooldown(rules::ActionCooldown::Each(20));
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+        for _ in 0..30 {
+            game.tick();
+        }
+
+        assert!(game.queue_action(actions::Action::Rotate).is_ok());
+        assert!(game.queue_action(actions::Action::MoveLeft).is_ok());
+        assert!(game.queue_action(actions::Action::RotateCcw).is_err());
+
+        let ticks = game.tick();
+        let ran: Vec<&recordings::EventKind> = game
+            .recording
+            .events
+            .iter()
+            .filter(|ev| ev.at == ticks)
+            .map(|ev| &ev.kind)
+            .collect();
+        assert_eq!(
+            ran,
+            vec![
+                &recordings::EventKind::Action(actions::Action::Rotate),
+                &recordings::EventKind::Action(actions::Action::MoveLeft),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_cooldown_allows_one_action_per_tick() {
+        let mut rules = rules::Rules::new();
+        rules.action_cooldown(rules::ActionCooldown::Shared(0));
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+        game.tick();
+
+        assert!(game.queue_action(actions::Action::Rotate).is_ok());
+        assert!(game.queue_action(actions::Action::MoveLeft).is_err());
+    }
+
+    #[test]
+    fn test_extended_placement_locks_when_out_of_resets() {
+        let mut rules = rules::Rules::new();
+        rules.lock_delay(100);
+        rules.lock_delay_on_hard_drop(true);
+        rules.lock_down(rules::LockDown::Extended(2));
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+        game.tick();
+
+        for action in [actions::Action::Drop, actions::Action::MoveLeft] {
+            game.queue_action(action).unwrap();
+            game.tick();
+            assert!(game.play_field.is_empty());
+        }
+
+        game.queue_action(actions::Action::MoveRight).unwrap();
+        game.tick();
+        assert!(!game.play_field.is_empty());
+    }
+
+    #[test]
+    fn test_hard_drop_after_running_out_of_resets_locks_at_once() {
+        let mut rules = rules::Rules::new();
+        rules.lock_delay(100);
+        rules.lock_down(rules::LockDown::Extended(2));
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+        let filled = |game: &Game| -> usize {
+            game.play_field
+                .matrix
+                .iter()
+                .flatten()
+                .filter(|loc| matches!(loc, playfield::Location::Filled(_)))
+                .count()
+        };
+
+        // Lands without a hard drop so the lock delay starts, then moves until the
+        // resets run out and it locks with lock delay to spare.
+        game.tick();
+        game.drop_fast();
+        game.tick();
+        for action in [actions::Action::MoveLeft, actions::Action::MoveRight] {
+            game.queue_action(action).unwrap();
+            game.tick();
+        }
+        assert_eq!(filled(&game), 4);
+
+        game.tick();
+        game.queue_action(actions::Action::Drop).unwrap();
+        game.tick();
+        assert_eq!(filled(&game), 8);
+    }
+
+    #[test]
+    fn test_hold_swaps_once_per_spawn() {
+        let mut rules = rules::Rules::new();
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+        let first = game.piece.tetromino;
+        let second = game.next_queue[0];
+        let third = game.next_queue[1];
+
+        // The first hold has nothing to swap with and pulls the next piece from the queue.
+        game.hold().unwrap();
+        assert_eq!(game.hold_piece, Some(first));
+        assert_eq!(game.piece.tetromino, second);
+        assert_eq!(game.next_queue[0], third);
+        assert!(!game.can_hold());
+
+        game.hold().unwrap();
+        assert_eq!(game.hold_piece, Some(first));
+        assert_eq!(game.piece.tetromino, second);
+
+        game.tick();
+        game.queue_action(actions::Action::Drop).unwrap();
+        game.tick();
+        game.tick();
+        assert_eq!(game.piece.tetromino, third);
+        assert!(game.can_hold());
+
+        game.hold().unwrap();
+        assert_eq!(game.hold_piece, Some(third));
+        assert_eq!(game.piece.tetromino, first);
+    }
+
+    #[test]
+    fn test_games_saved_before_hold_can_hold() {
+        let game = Game::new(4_188, rules::Rules::new(), 1, None).unwrap();
+        let mut saved = serde_json::to_value(&game).unwrap();
+        saved.as_object_mut().unwrap().remove("can_hold");
+
+        let loaded: Game = serde_json::from_value(saved).unwrap();
+        assert!(loaded.can_hold());
+    }
+
+    #[test]
+    fn test_games_saved_by_the_first_version_load_and_play_on() {
+        // Saved before hold existed, with a Pyramid falling, a Zig next and 8 cells filled.
+        let mut game = Game::from_json(include_str!(
+            "../../testdata/last_game_state_before_hold.json"
+        ))
+        .unwrap();
+        assert_eq!(game.ticks(), 400);
+        assert_eq!(game.piece.tetromino, tetrominos::Kind::Pyramid);
+        assert_eq!(game.next_queue, [tetrominos::Kind::Zig]);
+        assert_eq!(
+            game.gravity,
+            gravity::for_level(&gravity::Curve::Legacy, 1, 4_188)
+        );
+        assert!(game.can_hold());
+
+        game.tick();
+        game.queue_action(actions::Action::Drop).unwrap();
+        game.tick();
+        let filled = game
+            .play_field
+            .matrix
+            .iter()
+            .flatten()
+            .filter(|loc| matches!(loc, playfield::Location::Filled(_)))
+            .count();
+        assert_eq!(filled, 12);
+        assert_eq!(game.piece.tetromino, tetrominos::Kind::Zig);
+        assert_eq!(game.next_queue.len(), 1);
+    }
+
+    #[test]
+    fn test_next_queue_is_refilled_in_provider_order() {
+        let mut rules = rules::Rules::new();
+        rules.next_queue_size(3);
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+        let provided = |game: &Game| -> Vec<tetrominos::Kind> {
+            game.recording
+                .events
+                .iter()
+                .filter_map(|ev| match ev.kind {
+                    recordings::EventKind::PieceSpawned(k) => Some(k),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        // The first provided piece spawned, the rest wait in the queue.
+        assert_eq!(game.next_queue.len(), 3);
+        assert_eq!(
+            provided(&game)[1..],
+            game.next_queue.iter().copied().collect::<Vec<_>>()
+        );
+
+        for _ in 0..10 {
+            let front = game.next_queue[0];
+            game.grab_next_piece().unwrap();
+
+            assert_eq!(game.piece.tetromino, front);
+            assert_eq!(game.next_queue.len(), 3);
+            let provided = provided(&game);
+            assert_eq!(
+                provided[provided.len() - 3..],
+                game.next_queue.iter().copied().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_games_saved_before_the_next_queue_fill_it_on_spawn() {
+        let mut rules = rules::Rules::new();
+        rules.next_queue_size(3);
+        let game = Game::new(4_188, rules, 1, None).unwrap();
+        let mut value = serde_json::to_value(&game).unwrap();
+        value.as_object_mut().unwrap().remove("next_queue");
+
+        let mut game: Game = serde_json::from_value(value).unwrap();
+        assert!(game.next_queue.is_empty());
+        let provided_before = game.recording.events.len();
+        game.grab_next_piece().unwrap();
+
+        // The spawned piece and the queue behind it are all new from the provider.
+        let provided: Vec<tetrominos::Kind> = game.recording.events[provided_before..]
+            .iter()
+            .filter_map(|ev| match ev.kind {
+                recordings::EventKind::PieceSpawned(k) => Some(k),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(provided.len(), 4);
+        assert_eq!(game.piece.tetromino, provided[0]);
+        assert_eq!(
+            provided[1..],
+            game.next_queue.iter().copied().collect::<Vec<_>>()
+        );
+    }
+
+    // A T pointing up with its centre at row 11, col 6 and the given corners filled.
+    fn tspin_game(corners: &[(usize, usize)], kick: Option<u8>) -> Game {
+        let mut game = Game::new(4_188, rules::Rules::new(), 1, None).unwrap();
+        game.piece.tetromino = tetrominos::Kind::Pyramid;
+        game.piece.rotation = 0;
+        game.piece.x = 5;
+        game.piece.y = 10;
+        game.piece.rotation_kick = kick;
+        for (row, col) in corners {
+            game.play_field.matrix[*row][*col] = playfield::Location::Garbage;
+        }
+
+        game
+    }
+
+    #[test]
+    fn test_tspin_with_both_front_corners_is_full() {
+        let game = tspin_game(&[(10, 5), (10, 7), (12, 5)], Some(0));
+        assert_eq!(game.detect_tspin(), scoring::TSpin::Full);
+
+        let game = tspin_game(&[(10, 5), (10, 7), (12, 5), (12, 7)], Some(2));
+        assert_eq!(game.detect_tspin(), scoring::TSpin::Full);
+    }
+
+    #[test]
+    fn test_tspin_with_one_front_corner_is_mini_unless_the_last_kick_was_used() {
+        let game = tspin_game(&[(10, 5), (12, 5), (12, 7)], Some(0));
+        assert_eq!(game.detect_tspin(), scoring::TSpin::Mini);
+
+        let game = tspin_game(&[(10, 5), (12, 5), (12, 7)], Some(4));
+        assert_eq!(game.detect_tspin(), scoring::TSpin::Full);
+
+        let game = tspin_game(&[(10, 5), (12, 5)], Some(0));
+        assert_eq!(game.detect_tspin(), scoring::TSpin::None);
+    }
+
+    #[test]
+    fn test_only_srs_upgrades_last_kick_tspins() {
+        let mut game = tspin_game(&[(10, 5), (12, 5), (12, 7)], Some(4));
+        game.rules.rotation_system(rotation::Kind::Nintendo);
+        assert_eq!(game.detect_tspin(), scoring::TSpin::Mini);
+
+        // The ARS T points up in its rotation 2, a row lower in its box.
+        game.rules.rotation_system(rotation::Kind::Arika);
+        game.piece.rotation = 2;
+        game.piece.y = 9;
+        assert_eq!(game.detect_tspin(), scoring::TSpin::Mini);
+    }
+
+    #[test]
+    fn test_tspin_needs_a_t_piece_that_rotated_last() {
+        let mut game = tspin_game(&[(10, 5), (10, 7), (12, 5)], Some(0));
+        game.piece.tetromino = tetrominos::Kind::Hook;
+        assert_eq!(game.detect_tspin(), scoring::TSpin::None);
+
+        let mut game = tspin_game(&[(10, 5), (12, 5), (12, 7)], Some(0));
+        game.move_right();
+        game.move_left();
+        assert_eq!(game.piece.x, 5);
+        assert_eq!(game.detect_tspin(), scoring::TSpin::None);
+    }
+
+    #[test]
+    fn test_pieces_spawn_centred_in_the_well() {
+        for (width, spawn_x) in [(4, 3), (10, 6), (20, 11)] {
+            let mut rules = rules::Rules::new();
+            rules.well_width(width);
+            let mut game = Game::new(4_188, rules, 1, None).unwrap();
+            game.spawn_piece(tetrominos::Kind::Stick).unwrap();
+
+            assert_eq!(game.piece.x, spawn_x);
+        }
+    }
+
+    #[test]
+    fn test_wells_narrower_than_the_piece_set_are_rejected() {
+        let mut rules = rules::Rules::new();
+        rules.well_width(3);
+        assert!(Game::new(4_188, rules.clone(), 1, None).is_err());
+
+        rules.well_width(4);
+        rules.piece_set(polyominos::PieceSet::Custom(
+            include_str!("../../pentominos.txt").to_string(),
+        ));
+        assert_eq!(
+            Game::new(4_188, rules.clone(), 1, None).err(),
+            Some("the well needs to be 5 wide for the piece set".to_string())
+        );
+
+        rules.well_width(5);
+        assert!(Game::new(4_188, rules, 1, None).is_ok());
+    }
+
+    #[test]
+    fn test_line_clear_and_spawn_delays_hold_the_game() {
+        let mut rules = rules::Rules::new();
+        rules.line_clear_delay(3);
+        rules.spawn_delay(2);
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+        let bottom_row = game.play_field.rows + 3;
+        let well_cols = 3..game.play_field.cols + 3;
+        for col in well_cols.clone() {
+            game.play_field.matrix[bottom_row][col] = playfield::Location::Garbage;
+        }
+        let bottom_filled = |game: &Game| -> bool {
+            well_cols
+                .clone()
+                .any(|col| game.play_field.matrix[bottom_row][col] != playfield::Location::Empty)
+        };
+
+        game.tick();
+        game.queue_action(actions::Action::Drop).unwrap();
+        let locked_at = game.tick();
+        game.drain_events();
+        assert_eq!(game.state(), &State::ClearingLines(3));
+        assert_eq!(game.clearing_rows, vec![bottom_row]);
+        assert!(!bottom_filled(&game));
+        assert!(game.queue_action(actions::Action::MoveLeft).is_err());
+
+        game.tick();
+        assert_eq!(game.state(), &State::ClearingLines(2));
+        game.tick();
+        assert_eq!(game.state(), &State::ClearingLines(1));
+        assert!(!bottom_filled(&game));
+
+        // The well collapses on the last line clear tick, the stack drops onto the floor.
+        game.tick();
+        assert_eq!(game.state(), &State::Spawning(2));
+        assert!(game.clearing_rows.is_empty());
+        assert!(bottom_filled(&game));
+        assert!(game.queue_action(actions::Action::MoveLeft).is_err());
+
+        game.tick();
+        assert_eq!(game.state(), &State::Spawning(1));
+        let spawned_at = game.tick();
+        assert_eq!(game.state(), &State::Playing);
+        assert_eq!(spawned_at, locked_at + 5);
+
+        let spawns: Vec<usize> = game
+            .drain_events()
+            .into_iter()
+            .filter(|ev| matches!(ev.kind, events::EventKind::PieceSpawned(_)))
+            .map(|ev| ev.at)
+            .collect();
+        assert_eq!(spawns, vec![spawned_at]);
+        assert!(game.queue_action(actions::Action::MoveLeft).is_ok());
+    }
+
+    #[test]
+    fn test_hard_drop_emits_move_lock_and_spawn_events() {
+        let mut rules = rules::Rules::new();
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+        let spawned: Vec<events::EventKind> =
+            game.drain_events().into_iter().map(|ev| ev.kind).collect();
+        assert_eq!(
+            spawned,
+            vec![events::EventKind::PieceSpawned(game.piece.tetromino)]
+        );
+
+        game.tick();
+        game.queue_action(actions::Action::Drop).unwrap();
+        game.tick();
+        game.tick();
+
+        let kinds: Vec<events::EventKind> =
+            game.drain_events().into_iter().map(|ev| ev.kind).collect();
+        assert!(matches!(kinds[0], events::EventKind::PieceMoved { .. }));
+        assert!(matches!(kinds[1], events::EventKind::PieceLocked { .. }));
+        assert!(matches!(kinds[2], events::EventKind::PieceSpawned(_)));
+    }
+
+    #[test]
+    fn test_sprint_is_won_when_the_line_goal_is_reached() {
+        let mut rules = rules::Rules::new();
+        rules.mode(modes::GameMode::Sprint(1));
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+        let bottom_row = game.play_field.rows + 3;
+        for col in 3..game.play_field.cols + 3 {
+            game.play_field.matrix[bottom_row][col] =
+                playfield::Location::Filled(tetrominos::Kind::Stick);
+        }
+        assert_eq!(game.lines_remaining(), Some(1));
+
+        game.tick();
+        game.queue_action(actions::Action::Drop).unwrap();
+        while !game.is_finished() {
+            game.tick();
+        }
+
+        assert!(game.is_victory());
+        assert_eq!(game.lines_remaining(), Some(0));
+        assert_eq!(
+            game.recording.events.last().map(|ev| &ev.kind),
+            Some(&recordings::EventKind::Finished)
+        );
+    }
+
+    #[test]
+    fn test_ultra_ends_after_the_time_limit_in_ticks() {
+        let mut rules = rules::Rules::new();
+        rules.mode(modes::GameMode::Ultra(10));
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+
+        for _ in 0..20 {
+            game.tick();
+        }
+
+        assert!(game.is_victory());
+        assert_eq!(game.ticks, 10);
+        assert_eq!(game.time_remaining(), Some(time::Duration::ZERO));
+    }
+
+    #[test]
+    fn test_dig_is_won_when_the_garbage_is_cleared() {
+        let mut rules = rules::Rules::new();
+        rules.mode(modes::GameMode::Dig(1));
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+        let mut game = Game::new(4_188, rules.clone(), 1, None).unwrap();
+        let same_seed = Game::new(4_188, rules, 1, None).unwrap();
+        assert_eq!(game.play_field.matrix, same_seed.play_field.matrix);
+        assert_eq!(game.lines_remaining(), Some(1));
+
+        // Plug the hole so the first piece to lock clears the garbage row.
+        let bottom_row = game.play_field.rows + 3;
+        for col in 3..game.play_field.cols + 3 {
+            if game.play_field.matrix[bottom_row][col] == playfield::Location::Empty {
+                game.play_field.matrix[bottom_row][col] =
+                    playfield::Location::Filled(tetrominos::Kind::Stick);
+            }
+        }
+        game.play_field.matrix[bottom_row][3] = playfield::Location::Garbage;
+
+        game.tick();
+        game.queue_action(actions::Action::Drop).unwrap();
+        while !game.is_finished() {
+            game.tick();
+        }
+
+        assert!(game.is_victory());
+        assert_eq!(game.lines_remaining(), Some(0));
+    }
+
+    #[test]
+    fn test_custom_piece_set_deals_and_locks_its_pieces() {
+        let mut rules = rules::Rules::new();
+        rules.piece_set(polyominos::PieceSet::Custom(
+            include_str!("../../pentominos.txt").to_string(),
+        ));
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+        let mut game = Game::new(4_188, rules, 1, None).unwrap();
+        assert!(matches!(game.piece.tetromino, tetrominos::Kind::Custom(_)));
+        assert_eq!(game.piece_form().size(), 5);
+
+        game.tick();
+        game.queue_action(actions::Action::Drop).unwrap();
+        game.tick();
+        game.tick();
+
+        let filled = game
+            .play_field
+            .matrix
+            .iter()
+            .flatten()
+            .filter(|loc| matches!(loc, playfield::Location::Filled(_)))
+            .count();
+        assert_eq!(filled, 5);
+    }
+}
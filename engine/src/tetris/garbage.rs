@@ -0,0 +1,46 @@
+use crate::tetris::rng;
+
+use rand::Rng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+// Picks the hole column of each garbage row. It is seeded (and serialized with the game)
+// so the same seed always builds the same garbage, in play and in replays.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Generator {
+    rng: rng::SplitMix64,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Generator {
+        Generator {
+            rng: rng::SplitMix64::new(seed),
+        }
+    }
+
+    // The hole column (counted from the left of the well) for the next garbage row.
+    pub fn next_hole(&mut self, cols: usize) -> usize {
+        self.rng.gen_range(0..cols)
+    }
+}
+
+// The garbage seed of a game, derived from the game seed so it is not correlated with
+// the piece randomizer (which is seeded with the game seed itself).
+pub fn seed_for(game_seed: u64) -> u64 {
+    rng::SplitMix64::new(game_seed).next_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_holes() {
+        let mut a = Generator::new(42);
+        let mut b = Generator::new(42);
+
+        let holes: Vec<usize> = (0..20).map(|_| a.next_hole(10)).collect();
+        assert!(holes.iter().all(|h| *h < 10));
+        assert_eq!(holes, (0..20).map(|_| b.next_hole(10)).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,142 @@
+use serde;
+
+// Gravity is measured in 1/65536ths of a row per game tick, so ONE_G moves the piece
+// down one row every tick and anything above it moves the piece multiple rows per tick.
+pub const ONE_G: u32 = 65_536;
+pub const MAX_G: u32 = 20 * ONE_G;
+
+#[repr(u8)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub enum Curve {
+    // The fixed ticks per row table used before gravity curves existed.
+    Legacy,
+    Guideline,
+    // NES levels 0-29, level 1 here is NES level 0.
+    Nintendo,
+    // TGM internal levels 0-500, every level here is 10 TGM levels.
+    Arika,
+    // Gravity per level in 1/65536ths of a row per tick, the last entry is used for
+    // every level after it.
+    Custom(Vec<u32>),
+}
+
+pub fn for_level(curve: &Curve, level: u8, tick_rate_us: u64) -> u32 {
+    let level = level.max(1);
+    let gravity = match curve {
+        Curve::Legacy => {
+            // The legacy table moved the piece once every (speed + 1) ticks.
+            let ticks_per_row = legacy_speed(level) as u32 + 1;
+            ONE_G.div_ceil(ticks_per_row)
+        }
+        Curve::Guideline => {
+            let idx = (level as usize - 1).min(GUIDELINE_US_PER_ROW.len() - 1);
+            per_tick(tick_rate_us, ONE_G as u64, GUIDELINE_US_PER_ROW[idx])
+        }
+        Curve::Nintendo => {
+            let idx = (level as usize - 1).min(NES_FRAMES_PER_ROW.len() - 1);
+            per_tick(
+                tick_rate_us,
+                ONE_G as u64,
+                NES_FRAMES_PER_ROW[idx] * NES_FRAME_US,
+            )
+        }
+        Curve::Arika => {
+            let tgm_level = (level as u32 - 1) * 10;
+            let internal = TGM_GRAVITY
+                .iter()
+                .rev()
+                .find(|(from, _)| tgm_level >= *from)
+                .map_or(4, |(_, g)| *g);
+            // 20G in TGM means the piece lands instantly.
+            if internal >= 20 * 256 {
+                MAX_G
+            } else {
+                per_tick(tick_rate_us, internal * 256, TGM_FRAME_US)
+            }
+        }
+        Curve::Custom(table) => table
+            .get(level as usize - 1)
+            .or(table.last())
+            .copied()
+            .unwrap_or(ONE_G),
+    };
+
+    gravity.clamp(1, MAX_G)
+}
+
+// Converts `rows` (in 1/65536ths) every `us` microseconds into gravity per tick.
+fn per_tick(tick_rate_us: u64, rows: u64, us: u64) -> u32 {
+    (tick_rate_us * rows / us.max(1)).min(MAX_G as u64) as u32
+}
+
+const fn legacy_speed(level: u8) -> u8 {
+    match level {
+        1 => 70,
+        2 => 60,
+        3 => 51,
+        4 => 43,
+        5 => 36,
+        6 => 30,
+        7 => 25,
+        8 => 21,
+        9 => 18,
+        10 => 16,
+        11 => 15,
+        12 => 14,
+        13 => 13,
+        14 => 12,
+        15 => 11,
+        _ => 10,
+    }
+}
+
+// (0.8 - ((level - 1) * 0.007)) ^ (level - 1) seconds per row, for levels 1-20.
+#[rustfmt::skip]
+const GUIDELINE_US_PER_ROW: [u64; 20] = [
+    1_000_000, 793_000, 617_796, 472_729, 355_197, 262_004, 189_677, 134_735, 93_882, 64_152,
+    42_976, 28_218, 18_153, 11_439, 7_059, 4_264, 2_520, 1_457, 824, 455,
+];
+
+// The NES runs at 60.0988 frames per second.
+const NES_FRAME_US: u64 = 16_639;
+
+#[rustfmt::skip]
+const NES_FRAMES_PER_ROW: [u64; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6,
+    5, 5, 5, 4, 4, 4, 3, 3, 3, 2,
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 1,
+];
+
+const TGM_FRAME_US: u64 = 16_667;
+
+// (from TGM level, gravity in 1/256ths of a row per frame)
+#[rustfmt::skip]
+const TGM_GRAVITY: [(u32, u64); 30] = [
+    (0, 4), (30, 6), (35, 8), (40, 10), (50, 12), (60, 16), (70, 32), (80, 48),
+    (90, 64), (100, 80), (120, 96), (140, 112), (160, 128), (170, 144), (200, 4),
+    (220, 32), (230, 64), (233, 96), (236, 128), (239, 160), (243, 192), (247, 224),
+    (251, 256), (300, 512), (330, 768), (360, 1024), (400, 1280), (420, 1024),
+    (450, 768), (500, 5120),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_curve_matches_ticks_per_row() {
+        // Level 1 used to move the piece once every 71 ticks.
+        let gravity = for_level(&Curve::Legacy, 1, 4_188);
+        assert!(gravity * 70 < ONE_G);
+        assert!(gravity * 71 >= ONE_G);
+    }
+
+    #[test]
+    fn test_gravity_is_capped_at_20g() {
+        assert_eq!(for_level(&Curve::Arika, 60, 4_188), MAX_G);
+        assert_eq!(
+            for_level(&Curve::Custom(vec![100 * ONE_G]), 1, 4_188),
+            MAX_G
+        );
+    }
+}
@@ -0,0 +1,18 @@
+pub mod actions;
+pub mod attack;
+pub mod events;
+pub mod game;
+pub mod garbage;
+pub mod gravity;
+pub mod modes;
+pub mod playfield;
+pub mod polyominos;
+pub mod randomizers;
+pub mod recordings;
+pub mod rng;
+pub mod rotation;
+pub mod rules;
+pub mod scoring;
+pub mod shape_text;
+pub mod tetrominos;
+pub mod versus;
@@ -0,0 +1,50 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+    // Play until the stack tops out, the only mode before modes existed.
+    Marathon,
+    // Clear the line goal as fast as possible, the game is won when the goal is reached.
+    Sprint(u32),
+    // Score as much as possible before the time limit (in game ticks) runs out.
+    Ultra(usize),
+    // Start with the given number of garbage rows, the game is won when all of them
+    // have been cleared.
+    Dig(u32),
+}
+
+impl GameMode {
+    // Lines still needed to win, modes without a line goal have none. Dig counts the
+    // garbage rows left in the well instead, see Game::lines_remaining.
+    pub fn lines_remaining(&self, lines_cleared: u32) -> Option<u32> {
+        match self {
+            GameMode::Sprint(goal) => Some(goal.saturating_sub(lines_cleared)),
+            _ => None,
+        }
+    }
+
+    // Ticks left to play, modes without a time limit have none.
+    pub fn ticks_remaining(&self, ticks: usize) -> Option<usize> {
+        match self {
+            GameMode::Ultra(limit) => Some(limit.saturating_sub(ticks)),
+            _ => None,
+        }
+    }
+
+    pub fn is_time_up(&self, ticks: usize) -> bool {
+        self.ticks_remaining(ticks) == Some(0)
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameMode::Marathon => write!(f, "Marathon"),
+            GameMode::Sprint(goal) => write!(f, "Sprint {}", goal),
+            GameMode::Ultra(_) => write!(f, "Ultra"),
+            GameMode::Dig(rows) => write!(f, "Dig {}", rows),
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use crate::tetris::tetrominos;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Location {
+    Empty,
+    Edge,
+    Filled(tetrominos::Kind),
+    Garbage,
+}
+
+pub type Shape = tetrominos::Form;
+
+type Matrix = Vec<Vec<Location>>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlayField {
+    pub cols: usize,
+    pub rows: usize,
+    pub matrix: Matrix,
+}
+
+const ROWS_PADDING: usize = 6; // 2 bottom, 4 top
+const COLS_PADDING: usize = 6; // 3 left, 3 right
+
+pub const ERR_PLAYFIELD_MIN_SIZE: &str = "smallest possible playfield size is 4 rows x 4 cols";
+
+impl PlayField {
+    // The rows and cols specified here is for the size of the well (the inner part where the
+    // tetrominos(blocks) are placed). The total size of the playfield matrix will be larger
+    // to add required padding and space required for gameplay mechanics.
+    //
+    // Details of the padding are as follows:
+    // - TOP: 4 empty rows
+    // - LEFT: 3 edge columns
+    // - RIGHT: 3 edge columns
+    // - BOTTOM: 2 edge rows
+    pub fn new(rows: usize, cols: usize) -> Result<PlayField, &'static str> {
+        if rows < 4 || cols < 4 {
+            return Err(ERR_PLAYFIELD_MIN_SIZE);
+        }
+
+        let matrix_rows = rows + ROWS_PADDING;
+        let matrix_cols = cols + COLS_PADDING;
+
+        let mut pf = PlayField {
+            cols,
+            rows,
+            matrix: vec![vec![Location::Edge; matrix_cols]; matrix_rows],
+        };
+
+        // cut out the well
+        let row_offset = 4;
+        let col_offset = COLS_PADDING / 2;
+        for row in 0..rows + row_offset {
+            for col in col_offset..cols + col_offset {
+                pf.matrix[row][col] = Location::Empty;
+            }
+        }
+
+        Ok(pf)
+    }
+
+    pub fn well_x(&self) -> usize {
+        COLS_PADDING / 2
+    }
+
+    pub fn well_y(&self) -> usize {
+        4
+    }
+
+    pub fn has_collission(&self, shape_y: usize, shape_x: usize, shape: &Shape) -> bool {
+        let mut total: u8 = 0;
+
+        let mut shape_rows = shape.size();
+        let shape_height = shape.size();
+        if shape_y + shape_height >= self.rows + ROWS_PADDING {
+            shape_rows = self.rows + ROWS_PADDING - shape_y;
+        }
+
+        let mut shape_cols = shape.size();
+        let shape_width = shape.size();
+        if shape_x + shape_width >= self.cols + COLS_PADDING {
+            shape_cols = self.cols + COLS_PADDING - shape_x;
+        }
+
+        for row in 0..shape_rows {
+            for col in 0..shape_cols {
+                total += shape[row][col]
+                    & match self.matrix[shape_y + row][shape_x + col] {
+                        Location::Empty => 0,
+                        _default => 1,
+                    }
+            }
+        }
+
+        total > 0
+    }
+
+    // Matrix row indices of all the full rows in the well, from top to bottom.
+    pub fn full_rows(&self) -> Vec<usize> {
+        let row_offset = self.well_y();
+        let col_offset = self.well_x();
+
+        (row_offset..self.rows + row_offset)
+            .filter(|row| {
+                self.matrix[*row][col_offset..self.cols + col_offset]
+                    .iter()
+                    .all(|l| *l != Location::Empty)
+            })
+            .collect()
+    }
+
+    pub fn clear_full_rows(&mut self) -> u32 {
+        let row_offset = self.well_y();
+        let col_offset = self.well_x();
+
+        let mut lines_cleared = 0;
+
+        for row in row_offset..self.rows + row_offset {
+            let mut col_count = 0;
+            for col in col_offset..self.cols + col_offset {
+                if self.matrix[row][col] != Location::Empty {
+                    col_count += 1;
+                }
+            }
+            if col_count == self.cols {
+                for clear_col in col_offset..self.cols + col_offset {
+                    self.matrix[row][clear_col] = Location::Empty;
+                }
+                lines_cleared += 1;
+            }
+        }
+
+        lines_cleared
+    }
+
+    // True when there are no blocks left anywhere in the well, including the rows above it.
+    pub fn is_empty(&self) -> bool {
+        let row_offset = self.well_y();
+        let col_offset = self.well_x();
+
+        self.matrix[..self.rows + row_offset].iter().all(|row| {
+            row[col_offset..self.cols + col_offset]
+                .iter()
+                .all(|l| *l == Location::Empty)
+        })
+    }
+
+    // Pushes everything in the well (and the rows above it) up by one row and fills the
+    // bottom row with garbage, except for the hole column (counted from the left of the
+    // well). Returns true when blocks were pushed out of the top of the matrix.
+    pub fn insert_garbage_row(&mut self, hole: usize) -> bool {
+        let bottom_row = self.rows + self.well_y() - 1;
+        let col_offset = self.well_x();
+        let cols = col_offset..self.cols + col_offset;
+
+        let pushed_out = self.matrix[0][cols.clone()]
+            .iter()
+            .any(|l| *l != Location::Empty);
+
+        for row in 0..bottom_row {
+            for col in cols.clone() {
+                self.matrix[row][col] = self.matrix[row + 1][col];
+            }
+        }
+        for col in cols {
+            self.matrix[bottom_row][col] = if col - col_offset == hole {
+                Location::Empty
+            } else {
+                Location::Garbage
+            };
+        }
+
+        pushed_out
+    }
+
+    // The number of rows in the well that still have garbage in them.
+    pub fn garbage_rows(&self) -> usize {
+        let row_offset = self.well_y();
+        let col_offset = self.well_x();
+
+        self.matrix[row_offset..self.rows + row_offset]
+            .iter()
+            .filter(|row| row[col_offset..self.cols + col_offset].contains(&Location::Garbage))
+            .count()
+    }
+
+    // Removes the empty rows from the stack, everything above them moves down.
+    pub fn collapse(&mut self) {
+        let rows = self.rows + self.well_y();
+        let col_offset = self.well_x();
+        let cols = col_offset..self.cols + col_offset;
+
+        let kept: Vec<Vec<Location>> = (0..rows)
+            .rev()
+            .map(|row| self.matrix[row][cols.clone()].to_vec())
+            .filter(|row| row.iter().any(|l| *l != Location::Empty))
+            .collect();
+
+        for (i, row) in (0..rows).rev().enumerate() {
+            for (c, col) in cols.clone().enumerate() {
+                self.matrix[row][col] = kept.get(i).map_or(Location::Empty, |r| r[c]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_collission_empty() {
+        let pf = PlayField::new(10, 10).unwrap();
+        let shape = Shape::new([[1, 1, 1, 1], [1, 1, 1, 1], [1, 1, 1, 1], [1, 1, 1, 1]]);
+
+        assert!(!pf.has_collission(5, 5, &shape));
+    }
+
+    #[test]
+    fn test_has_collission_hit() {
+        let mut pf = PlayField::new(10, 10).unwrap();
+        pf.matrix[0][0] = Location::Filled(tetrominos::Kind::Hook);
+        let shape = Shape::new([[1, 1, 1, 1], [1, 1, 1, 1], [1, 1, 1, 1], [1, 1, 1, 1]]);
+
+        assert!(pf.has_collission(0, 0, &shape));
+    }
+
+    #[test]
+    fn test_new_rejects_wells_narrower_than_the_tetrominos() {
+        assert_eq!(PlayField::new(22, 3).err(), Some(ERR_PLAYFIELD_MIN_SIZE));
+        assert_eq!(PlayField::new(3, 10).err(), Some(ERR_PLAYFIELD_MIN_SIZE));
+        assert!(PlayField::new(22, 4).is_ok());
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let mut pf = PlayField::new(10, 10).unwrap();
+        assert!(pf.is_empty());
+
+        let (y, x) = (pf.well_y() + 9, pf.well_x());
+        pf.matrix[y][x] = Location::Filled(tetrominos::Kind::Hook);
+        assert!(!pf.is_empty());
+    }
+
+    #[test]
+    fn test_insert_garbage_row_pushes_the_stack_up() {
+        let mut pf = PlayField::new(10, 10).unwrap();
+        let (y, x) = (pf.well_y() + 9, pf.well_x());
+        pf.matrix[y][x] = Location::Filled(tetrominos::Kind::Hook);
+
+        assert!(!pf.insert_garbage_row(2));
+
+        assert_eq!(
+            pf.matrix[y - 1][x],
+            Location::Filled(tetrominos::Kind::Hook)
+        );
+        assert_eq!(pf.matrix[y][x + 2], Location::Empty);
+        assert_eq!(pf.matrix[y][x + 3], Location::Garbage);
+        assert_eq!(pf.garbage_rows(), 1);
+    }
+
+    #[test]
+    fn test_collapse_removes_adjacent_empty_rows() {
+        let mut pf = PlayField::new(10, 10).unwrap();
+        let (y, x) = (pf.well_y() + 7, pf.well_x());
+        pf.matrix[y][x] = Location::Garbage;
+
+        pf.collapse();
+
+        assert_eq!(pf.matrix[y + 2][x], Location::Garbage);
+        assert_eq!(pf.matrix[y][x], Location::Empty);
+    }
+}
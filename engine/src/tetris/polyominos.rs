@@ -0,0 +1,127 @@
+use crate::tetris::randomizers;
+use crate::tetris::shape_text;
+use crate::tetris::tetrominos;
+
+use serde::{Deserialize, Serialize};
+
+// The pieces a game is played with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PieceSet {
+    // The seven tetrominos, with the forms of the rotation system.
+    Tetrominos,
+    // Pieces defined in the tetrominos.txt format, see shape_text.
+    Custom(String),
+}
+
+impl PieceSet {
+    // The pieces of a custom set, the tetrominos have none.
+    pub fn polyominos(&self) -> Result<Vec<Polyomino>, String> {
+        match self {
+            PieceSet::Tetrominos => Ok(vec![]),
+            PieceSet::Custom(definitions) => parse(definitions),
+        }
+    }
+}
+
+// A piece of a custom piece set, it is dealt as tetrominos::Kind::Custom(index in the set).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Polyomino {
+    pub name: String,
+    // RGB, clients pick a colour for pieces without one.
+    pub colour: Option<(u8, u8, u8)>,
+    pub forms: [tetrominos::Form; 4],
+}
+
+// The kinds dealt for a piece set, the tetrominos when it has no custom pieces.
+pub fn kinds(polyominos: &[Polyomino]) -> Vec<tetrominos::Kind> {
+    if polyominos.is_empty() {
+        return randomizers::ALL_KINDS.to_vec();
+    }
+
+    (0..polyominos.len())
+        .map(|idx| tetrominos::Kind::Custom(idx as u8))
+        .collect()
+}
+
+// Parses piece definitions in the format of tetrominos.txt, see shape_text.
+pub fn parse(definitions: &str) -> Result<Vec<Polyomino>, String> {
+    let mut polyominos = vec![];
+    for definition in shape_text::parse(definitions)? {
+        let forms = definition
+            .forms
+            .iter()
+            .map(|rows| tetrominos::Form::from_rows(rows))
+            .collect::<Result<Vec<tetrominos::Form>, String>>()
+            .map_err(|e| format!("line {}: {}: {}", definition.line, definition.name, e))?;
+
+        polyominos.push(Polyomino {
+            name: definition.name,
+            colour: definition.colour,
+            forms: [forms[0], forms[1], forms[2], forms[3]],
+        });
+    }
+
+    if polyominos.is_empty() {
+        return Err("the piece set has no pieces".to_string());
+    }
+    if polyominos.len() > u8::MAX as usize {
+        return Err(format!("a piece set has at most {} pieces", u8::MAX));
+    }
+
+    Ok(polyominos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The spawn forms of the guideline tetrominos, written out so a bad edit of
+    // tetrominos.txt or of the build script that generates the built-in forms shows.
+    #[rustfmt::skip]
+    const SPAWN_FORMS: [(tetrominos::Kind, tetrominos::Form); 7] = [
+        (tetrominos::Kind::Stick, tetrominos::Form::new([[0,0,0,0], [1,1,1,1], [0,0,0,0], [0,0,0,0]])),
+        (tetrominos::Kind::Seven, tetrominos::Form::new([[0,0,1,0], [1,1,1,0], [0,0,0,0], [0,0,0,0]])),
+        (tetrominos::Kind::Hook, tetrominos::Form::new([[1,0,0,0], [1,1,1,0], [0,0,0,0], [0,0,0,0]])),
+        (tetrominos::Kind::Square, tetrominos::Form::new([[0,1,1,0], [0,1,1,0], [0,0,0,0], [0,0,0,0]])),
+        (tetrominos::Kind::Snake, tetrominos::Form::new([[0,1,1,0], [1,1,0,0], [0,0,0,0], [0,0,0,0]])),
+        (tetrominos::Kind::Pyramid, tetrominos::Form::new([[0,1,0,0], [1,1,1,0], [0,0,0,0], [0,0,0,0]])),
+        (tetrominos::Kind::Zig, tetrominos::Form::new([[1,1,0,0], [0,1,1,0], [0,0,0,0], [0,0,0,0]])),
+    ];
+
+    #[test]
+    fn test_tetrominos_txt_has_the_guideline_spawn_forms() {
+        let polyominos = parse(include_str!("../../tetrominos.txt")).unwrap();
+
+        assert_eq!(polyominos.len(), SPAWN_FORMS.len());
+        for (polyomino, (kind, spawn_form)) in polyominos.iter().zip(SPAWN_FORMS) {
+            assert_eq!(polyomino.name, format!("{:?}", kind));
+            assert_eq!(polyomino.forms[0], spawn_form);
+            // The other forms are checked to be rotations of it when parsing.
+            assert_eq!(polyomino.forms, tetrominos::from_kind(kind).unwrap().forms);
+        }
+    }
+
+    #[test]
+    fn test_pentominos_with_generated_rotations() {
+        let polyominos = parse(include_str!("../../pentominos.txt")).unwrap();
+
+        assert_eq!(polyominos.len(), 12);
+        for polyomino in polyominos.iter() {
+            assert_eq!(polyomino.forms[0].size(), 5);
+            assert!(polyomino.forms.iter().all(|form| form.cell_count() == 5));
+        }
+        assert_eq!(polyominos[0].colour, Some((0xf1, 0xd4, 0x48)));
+    }
+
+    #[test]
+    fn test_bad_definitions_report_the_line() {
+        assert_eq!(
+            parse("Bad {\n██.\n.x.\n...\n}"),
+            Err("line 3: unexpected 'x', cells are █ or .".to_string())
+        );
+        assert_eq!(
+            parse("Uneven {\n█.   █.   ..   .█\n█.   █.\n}").unwrap_err(),
+            "line 3: every line needs a row of each form"
+        );
+    }
+}
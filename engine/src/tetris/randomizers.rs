@@ -0,0 +1,326 @@
+use std::collections;
+
+use crate::tetris::game;
+use crate::tetris::rng;
+use crate::tetris::tetrominos;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use typetag;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[repr(u8)]
+pub enum Kind {
+    SevenBag = 1,
+    FourteenBag = 2,
+    // NES: roll once, reroll once if the piece repeats the previous one.
+    Nintendo = 3,
+    Uniform = 4,
+    // TGM: avoid the last 4 pieces with up to 6 rolls.
+    TGMHistory = 5,
+    // TGM3: 35 piece pool with drought compensation and a history of 4 with 6 rolls.
+    TGM3Pool = 6,
+}
+
+// Randomizers deal the given kinds, the tetrominos or the pieces of a custom piece set
+// (see polyominos::kinds).
+pub fn new(kind: &Kind, seed: u64, kinds: Vec<tetrominos::Kind>) -> Box<dyn game::PieceProvider> {
+    match kind {
+        Kind::SevenBag => Box::new(TetrominoBag::new(seed, 1, kinds)),
+        Kind::FourteenBag => Box::new(TetrominoBag::new(seed, 2, kinds)),
+        Kind::Nintendo => Box::new(NintendoRandomizer::new(seed, kinds)),
+        Kind::Uniform => Box::new(UniformRandomizer::new(seed, kinds)),
+        Kind::TGMHistory => Box::new(HistoryRandomizer::new(seed, kinds)),
+        Kind::TGM3Pool => Box::new(PoolRandomizer::new(seed, kinds)),
+    }
+}
+
+pub const ALL_KINDS: [tetrominos::Kind; 7] = [
+    tetrominos::Kind::Stick,
+    tetrominos::Kind::Square,
+    tetrominos::Kind::Pyramid,
+    tetrominos::Kind::Seven,
+    tetrominos::Kind::Snake,
+    tetrominos::Kind::Hook,
+    tetrominos::Kind::Zig,
+];
+
+// The TGM randomizers never deal an S, Z or O piece first.
+const FIRST_KINDS: [tetrominos::Kind; 4] = [
+    tetrominos::Kind::Stick,
+    tetrominos::Kind::Hook,
+    tetrominos::Kind::Seven,
+    tetrominos::Kind::Pyramid,
+];
+
+// Custom piece sets have no S, Z or O pieces to avoid, any of their pieces can be first.
+fn first_kinds(kinds: &[tetrominos::Kind]) -> Vec<tetrominos::Kind> {
+    let first: Vec<tetrominos::Kind> = FIRST_KINDS
+        .iter()
+        .filter(|k| kinds.contains(k))
+        .copied()
+        .collect();
+    if first.is_empty() {
+        kinds.to_vec()
+    } else {
+        first
+    }
+}
+
+// Providers saved before piece sets existed dealt the tetrominos.
+fn legacy_kinds() -> Vec<tetrominos::Kind> {
+    ALL_KINDS.to_vec()
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TetrominoBag {
+    pieces: Vec<tetrominos::Kind>,
+    #[serde(default = "legacy_copies")]
+    copies: u8,
+    #[serde(default = "legacy_kinds")]
+    kinds: Vec<tetrominos::Kind>,
+    #[serde(default = "legacy_rng")]
+    rng: rng::SplitMix64,
+}
+
+// Bags saved before the 14 bag existed held one of each kind.
+fn legacy_copies() -> u8 {
+    1
+}
+
+// Bags saved before they were seeded drew from the thread RNG, they go on from seed 0.
+fn legacy_rng() -> rng::SplitMix64 {
+    rng::SplitMix64::new(0)
+}
+
+impl TetrominoBag {
+    pub fn new(seed: u64, copies: u8, kinds: Vec<tetrominos::Kind>) -> TetrominoBag {
+        TetrominoBag {
+            pieces: Self::fill(copies, &kinds),
+            copies,
+            kinds,
+            rng: rng::SplitMix64::new(seed),
+        }
+    }
+
+    fn fill(copies: u8, kinds: &[tetrominos::Kind]) -> Vec<tetrominos::Kind> {
+        let mut pieces = vec![];
+        for _ in 0..copies.max(1) {
+            pieces.extend_from_slice(kinds);
+        }
+
+        pieces
+    }
+}
+
+#[typetag::serde]
+impl game::PieceProvider for TetrominoBag {
+    fn next(&mut self) -> Result<tetrominos::Kind, String> {
+        if self.pieces.is_empty() {
+            self.pieces = TetrominoBag::fill(self.copies, &self.kinds);
+        }
+
+        let n1: usize = self.rng.gen_range(0..self.pieces.len());
+
+        Ok(self.pieces.swap_remove(n1))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NintendoRandomizer {
+    previous: Option<tetrominos::Kind>,
+    #[serde(default = "legacy_kinds")]
+    kinds: Vec<tetrominos::Kind>,
+    rng: rng::SplitMix64,
+}
+
+impl NintendoRandomizer {
+    pub fn new(seed: u64, kinds: Vec<tetrominos::Kind>) -> NintendoRandomizer {
+        NintendoRandomizer {
+            previous: None,
+            kinds,
+            rng: rng::SplitMix64::new(seed),
+        }
+    }
+}
+
+#[typetag::serde]
+impl game::PieceProvider for NintendoRandomizer {
+    fn next(&mut self) -> Result<tetrominos::Kind, String> {
+        // The first roll has an extra 8th outcome which always causes a reroll.
+        let roll: usize = self.rng.gen_range(0..self.kinds.len() + 1);
+        let mut kind = self.kinds.get(roll).copied();
+        if kind.is_none() || kind == self.previous {
+            kind = Some(self.kinds[self.rng.gen_range(0..self.kinds.len())]);
+        }
+
+        self.previous = kind;
+
+        kind.ok_or("NINTENDO RANDOMIZER FAILED TO ROLL A PIECE".to_string())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UniformRandomizer {
+    #[serde(default = "legacy_kinds")]
+    kinds: Vec<tetrominos::Kind>,
+    rng: rng::SplitMix64,
+}
+
+impl UniformRandomizer {
+    pub fn new(seed: u64, kinds: Vec<tetrominos::Kind>) -> UniformRandomizer {
+        UniformRandomizer {
+            kinds,
+            rng: rng::SplitMix64::new(seed),
+        }
+    }
+}
+
+#[typetag::serde]
+impl game::PieceProvider for UniformRandomizer {
+    fn next(&mut self) -> Result<tetrominos::Kind, String> {
+        Ok(self.kinds[self.rng.gen_range(0..self.kinds.len())])
+    }
+}
+
+const HISTORY_ROLLS: usize = 6;
+
+#[derive(Serialize, Deserialize)]
+pub struct HistoryRandomizer {
+    history: collections::VecDeque<tetrominos::Kind>,
+    first: bool,
+    #[serde(default = "legacy_kinds")]
+    kinds: Vec<tetrominos::Kind>,
+    rng: rng::SplitMix64,
+}
+
+impl HistoryRandomizer {
+    pub fn new(seed: u64, kinds: Vec<tetrominos::Kind>) -> HistoryRandomizer {
+        HistoryRandomizer {
+            history: collections::VecDeque::from([tetrominos::Kind::Zig; 4]),
+            first: true,
+            kinds,
+            rng: rng::SplitMix64::new(seed),
+        }
+    }
+}
+
+#[typetag::serde]
+impl game::PieceProvider for HistoryRandomizer {
+    fn next(&mut self) -> Result<tetrominos::Kind, String> {
+        let first_kinds = first_kinds(&self.kinds);
+        let mut kind = first_kinds[self.rng.gen_range(0..first_kinds.len())];
+
+        if !self.first {
+            for _ in 0..HISTORY_ROLLS {
+                kind = self.kinds[self.rng.gen_range(0..self.kinds.len())];
+                if !self.history.contains(&kind) {
+                    break;
+                }
+            }
+        }
+        self.first = false;
+
+        self.history.pop_front();
+        self.history.push_back(kind);
+
+        Ok(kind)
+    }
+}
+
+const POOL_COPIES: usize = 5;
+
+#[derive(Serialize, Deserialize)]
+pub struct PoolRandomizer {
+    pool: Vec<tetrominos::Kind>,
+    // Least recently dealt kind first, used to hand pieces in a drought back to the pool.
+    drought_order: Vec<tetrominos::Kind>,
+    history: collections::VecDeque<tetrominos::Kind>,
+    first: bool,
+    #[serde(default = "legacy_kinds")]
+    kinds: Vec<tetrominos::Kind>,
+    rng: rng::SplitMix64,
+}
+
+impl PoolRandomizer {
+    pub fn new(seed: u64, kinds: Vec<tetrominos::Kind>) -> PoolRandomizer {
+        let mut pool = vec![];
+        for _ in 0..POOL_COPIES {
+            pool.extend_from_slice(&kinds);
+        }
+
+        PoolRandomizer {
+            pool,
+            drought_order: kinds.clone(),
+            history: collections::VecDeque::from([
+                tetrominos::Kind::Snake,
+                tetrominos::Kind::Zig,
+                tetrominos::Kind::Snake,
+                tetrominos::Kind::Zig,
+            ]),
+            first: true,
+            kinds,
+            rng: rng::SplitMix64::new(seed),
+        }
+    }
+}
+
+#[typetag::serde]
+impl game::PieceProvider for PoolRandomizer {
+    fn next(&mut self) -> Result<tetrominos::Kind, String> {
+        let first_kinds = first_kinds(&self.kinds);
+        let mut kind = first_kinds[self.rng.gen_range(0..first_kinds.len())];
+        let mut idx = self.pool.iter().position(|k| *k == kind).unwrap_or(0);
+
+        if !self.first {
+            for roll in 0..HISTORY_ROLLS {
+                idx = self.rng.gen_range(0..self.pool.len());
+                kind = self.pool[idx];
+                if !self.history.contains(&kind) || roll == HISTORY_ROLLS - 1 {
+                    break;
+                }
+                // A rejected roll nudges the pool towards the most droughted kind.
+                self.pool[idx] = self.drought_order[0];
+            }
+        }
+        self.first = false;
+
+        self.drought_order.retain(|k| *k != kind);
+        self.drought_order.push(kind);
+        self.pool[idx] = self.drought_order[0];
+
+        self.history.pop_front();
+        self.history.push_back(kind);
+
+        Ok(kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetris::game::PieceProvider;
+
+    #[test]
+    fn test_bag_deals_every_kind_per_bag() {
+        let mut bag = TetrominoBag::new(42, 2, ALL_KINDS.to_vec());
+        let mut dealt = vec![];
+        for _ in 0..14 {
+            dealt.push(bag.next().unwrap());
+        }
+
+        for kind in ALL_KINDS {
+            assert_eq!(dealt.iter().filter(|k| **k == kind).count(), 2);
+        }
+    }
+
+    #[test]
+    fn test_tgm_randomizers_never_start_with_s_z_or_o() {
+        for seed in 0..50 {
+            for kind in [Kind::TGMHistory, Kind::TGM3Pool] {
+                let first = new(&kind, seed, ALL_KINDS.to_vec()).next().unwrap();
+                assert!(FIRST_KINDS.contains(&first));
+            }
+        }
+    }
+}
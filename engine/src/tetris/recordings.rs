@@ -0,0 +1,94 @@
+use crate::tetris::actions;
+use crate::tetris::game;
+use crate::tetris::tetrominos;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum EventKind {
+    Pause,
+    Unpause,
+    GameOver(game::GameOverReason),
+    // The game mode goal was reached (or the versus opponent topped out), the game is won.
+    Finished,
+    // Garbage lines sent by the versus opponent.
+    GarbageReceived(u32),
+    PieceSpawned(tetrominos::Kind),
+    Action(actions::Action),
+    Combo(u32),
+    BackToBack(u32),
+    PerfectClear,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Event {
+    pub kind: EventKind,
+    pub at: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Recording {
+    pub events: Vec<Event>,
+}
+
+impl Recording {
+    pub fn new() -> Recording {
+        Recording { events: vec![] }
+    }
+
+    pub fn push_action(&mut self, at: usize, action: actions::Action) {
+        self.events.push(Event {
+            kind: EventKind::Action(action),
+            at,
+        })
+    }
+
+    pub fn push_piece(&mut self, at: usize, k: tetrominos::Kind) {
+        self.events.push(Event {
+            kind: EventKind::PieceSpawned(k),
+            at,
+        })
+    }
+
+    pub fn push_combo(&mut self, at: usize, combo: u32) {
+        self.events.push(Event {
+            kind: EventKind::Combo(combo),
+            at,
+        })
+    }
+
+    pub fn push_back_to_back(&mut self, at: usize, streak: u32) {
+        self.events.push(Event {
+            kind: EventKind::BackToBack(streak),
+            at,
+        })
+    }
+
+    pub fn push_garbage_received(&mut self, at: usize, lines: u32) {
+        self.events.push(Event {
+            kind: EventKind::GarbageReceived(lines),
+            at,
+        })
+    }
+
+    pub fn push_perfect_clear(&mut self, at: usize) {
+        self.events.push(Event {
+            kind: EventKind::PerfectClear,
+            at,
+        })
+    }
+
+    pub fn finished(&mut self, at: usize) {
+        self.events.push(Event {
+            kind: EventKind::Finished,
+            at,
+        })
+    }
+
+    pub fn gameover(&mut self, at: usize, reason: game::GameOverReason) {
+        self.events.push(Event {
+            kind: EventKind::GameOver(reason),
+            at,
+        })
+    }
+}
@@ -0,0 +1,269 @@
+use crate::tetris::playfield;
+use crate::tetris::tetrominos;
+
+use serde;
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[repr(u8)]
+pub enum Kind {
+    // One cell left then one cell right, the behaviour before rotation systems existed.
+    Basic = 1,
+    SuperRotation = 2,
+    Nintendo = 3,
+    Arika = 4,
+}
+
+// Kick offsets are (x, y) in playfield coordinates, so a positive y moves the piece
+// DOWN the well. The first offset is always (0, 0) which is the unkicked rotation.
+pub type Kick = (i8, i8);
+
+pub trait System {
+    // The forms of the built in tetrominos, custom pieces bring their own forms.
+    fn forms(&self, k: tetrominos::Kind) -> Option<&'static [tetrominos::Form; 4]> {
+        tetrominos::from_kind(k).map(|t| &t.forms)
+    }
+
+    fn kicks(&self, k: tetrominos::Kind, from: u8, to: u8) -> &'static [Kick];
+
+    // Called before any kick (other than the first, unkicked test) is attempted.
+    fn can_kick(
+        &self,
+        _pf: &playfield::PlayField,
+        _k: tetrominos::Kind,
+        _y: usize,
+        _x: usize,
+        _form: &tetrominos::Form,
+    ) -> bool {
+        true
+    }
+}
+
+pub fn from_kind(kind: &Kind) -> &'static dyn System {
+    match kind {
+        Kind::Basic => &Basic,
+        Kind::SuperRotation => &SuperRotation,
+        Kind::Nintendo => &Nintendo,
+        Kind::Arika => &Arika,
+    }
+}
+
+const NO_KICKS: [Kick; 1] = [(0, 0)];
+
+pub struct Basic;
+
+const BASIC_KICKS: [Kick; 3] = [(0, 0), (-1, 0), (1, 0)];
+
+impl System for Basic {
+    fn kicks(&self, _k: tetrominos::Kind, _from: u8, _to: u8) -> &'static [Kick] {
+        &BASIC_KICKS
+    }
+}
+
+pub struct Nintendo;
+
+impl System for Nintendo {
+    fn kicks(&self, _k: tetrominos::Kind, _from: u8, _to: u8) -> &'static [Kick] {
+        &NO_KICKS
+    }
+}
+
+// Super Rotation System (Tetris Guideline).
+// The rotation states 0, R, 2, L map to forms 0, 1, 2, 3 in tetrominos.rs.
+// The guideline defines no kicks for 180 rotations so those only test (0, 0).
+pub struct SuperRotation;
+
+#[rustfmt::skip]
+const SRS_JLSTZ_KICKS: [[Kick; 5]; 8] = [
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // 0 -> R
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // R -> 0
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // R -> 2
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // 2 -> R
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // 2 -> L
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // L -> 2
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // L -> 0
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // 0 -> L
+];
+
+#[rustfmt::skip]
+const SRS_I_KICKS: [[Kick; 5]; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],   // 0 -> R
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],   // R -> 0
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],   // R -> 2
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],   // 2 -> R
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],   // 2 -> L
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],   // L -> 2
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],   // L -> 0
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],   // 0 -> L
+];
+
+fn srs_table_index(from: u8, to: u8) -> Option<usize> {
+    match (from, to) {
+        (0, 1) => Some(0),
+        (1, 0) => Some(1),
+        (1, 2) => Some(2),
+        (2, 1) => Some(3),
+        (2, 3) => Some(4),
+        (3, 2) => Some(5),
+        (3, 0) => Some(6),
+        (0, 3) => Some(7),
+        _ => None,
+    }
+}
+
+impl System for SuperRotation {
+    fn kicks(&self, k: tetrominos::Kind, from: u8, to: u8) -> &'static [Kick] {
+        let Some(idx) = srs_table_index(from, to) else {
+            return &NO_KICKS;
+        };
+
+        match k {
+            tetrominos::Kind::Square => &NO_KICKS,
+            tetrominos::Kind::Stick => &SRS_I_KICKS[idx],
+            _ => &SRS_JLSTZ_KICKS[idx],
+        }
+    }
+}
+
+// Arika Rotation System (TGM series).
+// Pieces sit flat side down inside their bounding box and only kick one cell right
+// and then one cell left. The I piece never kicks.
+pub struct Arika;
+
+const ARS_KICKS: [Kick; 3] = [(0, 0), (1, 0), (-1, 0)];
+
+#[rustfmt::skip]
+const ARS_STICK: [tetrominos::Form; 4] = [
+    tetrominos::Form::new([[0,0,0,0], [1,1,1,1], [0,0,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,1,0], [0,0,1,0], [0,0,1,0], [0,0,1,0]]),
+    tetrominos::Form::new([[0,0,0,0], [1,1,1,1], [0,0,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,1,0], [0,0,1,0], [0,0,1,0], [0,0,1,0]]),
+];
+
+#[rustfmt::skip]
+const ARS_SEVEN: [tetrominos::Form; 4] = [
+    tetrominos::Form::new([[0,0,0,0], [1,1,1,0], [1,0,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[1,1,0,0], [0,1,0,0], [0,1,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,0,0], [0,0,1,0], [1,1,1,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,1,0,0], [0,1,0,0], [0,1,1,0], [0,0,0,0]]),
+];
+
+#[rustfmt::skip]
+const ARS_HOOK: [tetrominos::Form; 4] = [
+    tetrominos::Form::new([[0,0,0,0], [1,1,1,0], [0,0,1,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,1,0,0], [0,1,0,0], [1,1,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,0,0], [1,0,0,0], [1,1,1,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,1,1,0], [0,1,0,0], [0,1,0,0], [0,0,0,0]]),
+];
+
+#[rustfmt::skip]
+const ARS_SQUARE: [tetrominos::Form; 4] = [
+    tetrominos::Form::new([[0,0,0,0], [0,1,1,0], [0,1,1,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,0,0], [0,1,1,0], [0,1,1,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,0,0], [0,1,1,0], [0,1,1,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,0,0], [0,1,1,0], [0,1,1,0], [0,0,0,0]]),
+];
+
+#[rustfmt::skip]
+const ARS_SNAKE: [tetrominos::Form; 4] = [
+    tetrominos::Form::new([[0,0,0,0], [0,1,1,0], [1,1,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[1,0,0,0], [1,1,0,0], [0,1,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,0,0], [0,1,1,0], [1,1,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[1,0,0,0], [1,1,0,0], [0,1,0,0], [0,0,0,0]]),
+];
+
+#[rustfmt::skip]
+const ARS_PYRAMID: [tetrominos::Form; 4] = [
+    tetrominos::Form::new([[0,0,0,0], [1,1,1,0], [0,1,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,1,0,0], [1,1,0,0], [0,1,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,0,0], [0,1,0,0], [1,1,1,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,1,0,0], [0,1,1,0], [0,1,0,0], [0,0,0,0]]),
+];
+
+#[rustfmt::skip]
+const ARS_ZIG: [tetrominos::Form; 4] = [
+    tetrominos::Form::new([[0,0,0,0], [1,1,0,0], [0,1,1,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,1,0], [0,1,1,0], [0,1,0,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,0,0], [1,1,0,0], [0,1,1,0], [0,0,0,0]]),
+    tetrominos::Form::new([[0,0,1,0], [0,1,1,0], [0,1,0,0], [0,0,0,0]]),
+];
+
+impl System for Arika {
+    fn forms(&self, k: tetrominos::Kind) -> Option<&'static [tetrominos::Form; 4]> {
+        match k {
+            tetrominos::Kind::Stick => Some(&ARS_STICK),
+            tetrominos::Kind::Seven => Some(&ARS_SEVEN),
+            tetrominos::Kind::Hook => Some(&ARS_HOOK),
+            tetrominos::Kind::Square => Some(&ARS_SQUARE),
+            tetrominos::Kind::Snake => Some(&ARS_SNAKE),
+            tetrominos::Kind::Pyramid => Some(&ARS_PYRAMID),
+            tetrominos::Kind::Zig => Some(&ARS_ZIG),
+            tetrominos::Kind::Custom(_) => None,
+        }
+    }
+
+    fn kicks(&self, k: tetrominos::Kind, _from: u8, _to: u8) -> &'static [Kick] {
+        match k {
+            tetrominos::Kind::Stick | tetrominos::Kind::Square => &NO_KICKS,
+            _ => &ARS_KICKS,
+        }
+    }
+
+    // The centre column rule: L, J and T pieces may not kick when the first blocked
+    // cell (scanning the 3x3 box row by row) is in the middle column.
+    fn can_kick(
+        &self,
+        pf: &playfield::PlayField,
+        k: tetrominos::Kind,
+        y: usize,
+        x: usize,
+        form: &tetrominos::Form,
+    ) -> bool {
+        if !matches!(
+            k,
+            tetrominos::Kind::Seven | tetrominos::Kind::Hook | tetrominos::Kind::Pyramid
+        ) {
+            return true;
+        }
+
+        for (row, cells) in form.iter().enumerate().take(3) {
+            for (col, cell) in cells.iter().enumerate().take(3) {
+                if *cell == 0 {
+                    continue;
+                }
+                if pf.matrix[y + row][x + col] != playfield::Location::Empty {
+                    return col != 1;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srs_kicks_mirror_reverse_rotation() {
+        for (from, to) in [(0, 1), (1, 2), (2, 3), (3, 0)] {
+            for k in [tetrominos::Kind::Stick, tetrominos::Kind::Pyramid] {
+                let forward = SuperRotation.kicks(k, from, to);
+                let reverse = SuperRotation.kicks(k, to, from);
+                for (f, r) in forward.iter().zip(reverse.iter()) {
+                    assert_eq!((f.0, f.1), (-r.0, -r.1));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ars_centre_column_rule() {
+        let mut pf = playfield::PlayField::new(10, 10).unwrap();
+        let form = &Arika.forms(tetrominos::Kind::Pyramid).unwrap()[1];
+        pf.matrix[5][6] = playfield::Location::Filled(tetrominos::Kind::Hook);
+
+        assert!(!Arika.can_kick(&pf, tetrominos::Kind::Pyramid, 5, 5, form));
+        assert!(Arika.can_kick(&pf, tetrominos::Kind::Snake, 5, 5, form));
+    }
+}
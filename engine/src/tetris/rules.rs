@@ -0,0 +1,244 @@
+use crate::tetris::attack;
+use crate::tetris::gravity;
+use crate::tetris::modes;
+use crate::tetris::polyominos;
+use crate::tetris::randomizers;
+use crate::tetris::rotation;
+use crate::tetris::scoring;
+
+use serde;
+
+#[repr(u8)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub enum ActionCooldown {
+    Shared(u8),
+    Each(u8),
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub enum LockDown {
+    // Moving left or right resets the lock delay, rotating does not.
+    MoveReset,
+    // Every move and rotation resets the lock delay.
+    Infinite,
+    // Moves and rotations reset the lock delay up to the limit, the limit starts over
+    // when the piece reaches a new lowest row. Out of resets means locking on landing.
+    Extended(u8),
+    // Only moving down to a new lowest row resets the lock delay.
+    StepReset,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub enum LevelGoal {
+    // 4 lines times the current level, the goal used before level goals existed.
+    Legacy,
+    Fixed(u32),
+    // Lines needed per level, the last entry is used for every level after it.
+    Custom(Vec<u32>),
+}
+
+impl LevelGoal {
+    pub fn lines_for_level(&self, level: u8) -> u32 {
+        match self {
+            LevelGoal::Legacy => 4 * level as u32,
+            LevelGoal::Fixed(lines) => *lines,
+            LevelGoal::Custom(table) => table
+                .get((level.max(1) - 1) as usize)
+                .or(table.last())
+                .copied()
+                .unwrap_or(10),
+        }
+    }
+}
+
+// Rules saved before the rotation system was configurable always used the basic kicks.
+fn legacy_rotation_system() -> rotation::Kind {
+    rotation::Kind::Basic
+}
+
+// Rules saved before the next queue existed only had a single next piece preview.
+fn legacy_next_queue_size() -> u8 {
+    1
+}
+
+// Rules saved before the randomizer was configurable always used the 7 bag.
+fn legacy_randomizer() -> randomizers::Kind {
+    randomizers::Kind::SevenBag
+}
+
+// Rules saved before the well size was configurable always used a 10 x 22 well.
+fn legacy_well_width() -> usize {
+    10
+}
+
+fn legacy_well_height() -> usize {
+    22
+}
+
+// Rules saved before gravity curves and level goals existed used the legacy tables.
+fn legacy_gravity_curve() -> gravity::Curve {
+    gravity::Curve::Legacy
+}
+
+fn legacy_level_goal() -> LevelGoal {
+    LevelGoal::Legacy
+}
+
+// Rules saved before game modes existed were all marathon games.
+fn legacy_mode() -> modes::GameMode {
+    modes::GameMode::Marathon
+}
+
+// Rules saved before versus existed never sent garbage, the guideline table is as good
+// a default as any.
+fn legacy_attack_table() -> attack::AttackTable {
+    attack::AttackTable::Guideline
+}
+
+// Rules saved before piece sets existed played with the tetrominos.
+fn legacy_piece_set() -> polyominos::PieceSet {
+    polyominos::PieceSet::Tetrominos
+}
+
+// Rules saved before lock down modes existed reset the lock delay on every move.
+fn legacy_lock_down() -> LockDown {
+    LockDown::MoveReset
+}
+
+// Rules saved before the delays existed spawned the next piece on the tick of the lock.
+fn legacy_delay() -> u32 {
+    0
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Rules {
+    #[serde(default = "legacy_mode")]
+    pub mode: modes::GameMode,
+    pub lock_delay: u32,
+    pub lock_delay_on_hard_drop: bool,
+    #[serde(default = "legacy_lock_down")]
+    pub lock_down: LockDown,
+    // Ticks between a piece locking (or the well collapsing) and the next piece spawning.
+    #[serde(default = "legacy_delay")]
+    pub spawn_delay: u32,
+    // Ticks between full rows clearing and the well collapsing.
+    #[serde(default = "legacy_delay")]
+    pub line_clear_delay: u32,
+    pub wall_kicks: bool,
+    #[serde(default = "legacy_well_width")]
+    pub well_width: usize,
+    #[serde(default = "legacy_well_height")]
+    pub well_height: usize,
+    #[serde(default = "legacy_gravity_curve")]
+    pub gravity_curve: gravity::Curve,
+    #[serde(default = "legacy_level_goal")]
+    pub level_goal: LevelGoal,
+    #[serde(default = "legacy_rotation_system")]
+    pub rotation_system: rotation::Kind,
+    #[serde(default = "legacy_next_queue_size")]
+    pub next_queue_size: u8,
+    #[serde(default = "legacy_randomizer")]
+    pub randomizer: randomizers::Kind,
+    pub scoring_system: scoring::Kind,
+    pub action_cooldown: ActionCooldown,
+    // Garbage sent to the opponent for line clears in versus.
+    #[serde(default = "legacy_attack_table")]
+    pub attack_table: attack::AttackTable,
+    #[serde(default = "legacy_piece_set")]
+    pub piece_set: polyominos::PieceSet,
+}
+
+impl Rules {
+    pub fn new() -> Rules {
+        Rules {
+            mode: modes::GameMode::Marathon,
+            lock_delay: 0,
+            lock_delay_on_hard_drop: false,
+            lock_down: LockDown::Extended(15),
+            spawn_delay: 0,
+            line_clear_delay: 0,
+            wall_kicks: true,
+            well_width: 10,
+            well_height: 22,
+            gravity_curve: gravity::Curve::Guideline,
+            level_goal: LevelGoal::Fixed(10),
+            rotation_system: rotation::Kind::SuperRotation,
+            next_queue_size: 5,
+            randomizer: randomizers::Kind::SevenBag,
+            scoring_system: scoring::Kind::OriginalBPS,
+            action_cooldown: ActionCooldown::Shared(20), // 20 ticks = 80ms cooldown
+            attack_table: attack::AttackTable::Guideline,
+            piece_set: polyominos::PieceSet::Tetrominos,
+        }
+    }
+
+    pub fn mode(&mut self, mode: modes::GameMode) {
+        self.mode = mode
+    }
+
+    pub fn lock_delay(&mut self, ld: u32) {
+        self.lock_delay = ld
+    }
+
+    pub fn lock_delay_on_hard_drop(&mut self, v: bool) {
+        self.lock_delay_on_hard_drop = v
+    }
+
+    pub fn lock_down(&mut self, mode: LockDown) {
+        self.lock_down = mode
+    }
+
+    pub fn spawn_delay(&mut self, ticks: u32) {
+        self.spawn_delay = ticks
+    }
+
+    pub fn line_clear_delay(&mut self, ticks: u32) {
+        self.line_clear_delay = ticks
+    }
+
+    pub fn well_width(&mut self, width: usize) {
+        self.well_width = width
+    }
+
+    pub fn well_height(&mut self, height: usize) {
+        self.well_height = height
+    }
+
+    pub fn gravity_curve(&mut self, curve: gravity::Curve) {
+        self.gravity_curve = curve
+    }
+
+    pub fn level_goal(&mut self, goal: LevelGoal) {
+        self.level_goal = goal
+    }
+
+    pub fn rotation_system(&mut self, kind: rotation::Kind) {
+        self.rotation_system = kind
+    }
+
+    pub fn next_queue_size(&mut self, size: u8) {
+        self.next_queue_size = size
+    }
+
+    pub fn randomizer(&mut self, kind: randomizers::Kind) {
+        self.randomizer = kind
+    }
+
+    pub fn scoring_system(&mut self, kind: scoring::Kind) {
+        self.scoring_system = kind
+    }
+
+    pub fn action_cooldown(&mut self, action_cooldown: ActionCooldown) {
+        self.action_cooldown = action_cooldown
+    }
+
+    pub fn attack_table(&mut self, table: attack::AttackTable) {
+        self.attack_table = table
+    }
+
+    pub fn piece_set(&mut self, set: polyominos::PieceSet) {
+        self.piece_set = set
+    }
+}
@@ -0,0 +1,327 @@
+use serde;
+use typetag;
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[repr(u8)]
+pub enum Kind {
+    OriginalBPS = 1,
+    OriginalSega = 2,
+    OriginalNintendo = 3,
+    DS = 4,
+    Guideline = 5,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+// Everything a scoring system gets to know about a piece locking into the well.
+#[derive(Debug, Clone)]
+pub struct ClearEvent {
+    pub level: u8,
+    pub lines: u8,
+    pub tspin: TSpin,
+    // The clear is difficult and so was the previous line clear.
+    pub back_to_back: bool,
+    // Number of consecutive piece locks that cleared lines, including this one.
+    pub combo: u32,
+    // The well is completely empty after the clear.
+    pub perfect_clear: bool,
+}
+
+impl ClearEvent {
+    // Tetrises and t-spins that clear lines are difficult and can chain back-to-back.
+    pub fn is_difficult(&self) -> bool {
+        self.lines >= 4 || (self.lines > 0 && self.tspin != TSpin::None)
+    }
+}
+
+#[typetag::serde(tag = "type")]
+pub trait System {
+    fn lines_cleared(&mut self, clear: &ClearEvent) -> u32;
+
+    fn dropped(&mut self, _rows: u32, _hard: bool) -> u32 {
+        0
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct OriginalBPS {
+    points: u32,
+    lines_cleared: u32,
+}
+
+impl OriginalBPS {
+    pub fn new() -> OriginalBPS {
+        OriginalBPS {
+            points: 0,
+            lines_cleared: 0,
+        }
+    }
+}
+
+#[typetag::serde]
+impl System for OriginalBPS {
+    fn lines_cleared(&mut self, clear: &ClearEvent) -> u32 {
+        let points = match clear.lines {
+            0 => 0,
+            1 => 40,
+            2 => 100,
+            3 => 300,
+            _ => 1200,
+        };
+        self.points += points;
+
+        points
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct OriginalSega {
+    points: u32,
+    lines_cleared: u32,
+}
+
+impl OriginalSega {
+    pub fn new() -> OriginalSega {
+        OriginalSega {
+            points: 0,
+            lines_cleared: 0,
+        }
+    }
+}
+
+#[typetag::serde]
+impl System for OriginalSega {
+    fn lines_cleared(&mut self, clear: &ClearEvent) -> u32 {
+        let count = clear.lines;
+        let points = match clear.level {
+            1..=2 => match count {
+                1 => 100,
+                2 => 400,
+                3 => 900,
+                4 => 2000,
+                _ => 0,
+            },
+            3..=4 => match count {
+                1 => 200,
+                2 => 800,
+                3 => 1800,
+                4 => 4000,
+                _ => 0,
+            },
+            5..=6 => match count {
+                1 => 300,
+                2 => 1200,
+                3 => 2700,
+                4 => 6000,
+                _ => 0,
+            },
+            7..=8 => match count {
+                1 => 400,
+                2 => 1600,
+                3 => 3600,
+                4 => 8000,
+                _ => 0,
+            },
+            _ => match count {
+                1 => 500,
+                2 => 2000,
+                3 => 4500,
+                4 => 10000,
+                _ => 0,
+            },
+        };
+
+        self.points += points;
+
+        points
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct OriginalNintendo {
+    points: u32,
+    lines_cleared: u32,
+}
+
+impl OriginalNintendo {
+    pub fn new() -> OriginalNintendo {
+        OriginalNintendo {
+            points: 0,
+            lines_cleared: 0,
+        }
+    }
+}
+
+#[typetag::serde]
+impl System for OriginalNintendo {
+    fn lines_cleared(&mut self, clear: &ClearEvent) -> u32 {
+        let level = clear.level;
+        let points = match clear.lines {
+            1 => 40 * level as u32,
+            2 => 100 * level as u32,
+            3 => 300 * level as u32,
+            4 => 1200 * level as u32,
+            _ => 0,
+        };
+        self.points += points;
+
+        points
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct DS {
+    points: u32,
+    lines_cleared: u32,
+}
+
+impl DS {
+    pub fn new() -> DS {
+        DS {
+            points: 0,
+            lines_cleared: 0,
+        }
+    }
+}
+
+#[typetag::serde]
+impl System for DS {
+    fn lines_cleared(&mut self, clear: &ClearEvent) -> u32 {
+        let level = clear.level;
+        let points = match clear.lines {
+            1 => 800 * level as u32,
+            2 => 1200 * level as u32,
+            3 => 1800 * level as u32,
+            4 => 2000 * level as u32,
+            _ => 0,
+        };
+        self.points += points;
+
+        points
+    }
+}
+
+// Tetris Guideline scoring with t-spins, back-to-back bonuses, combos and drop points.
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Guideline {
+    points: u32,
+    lines_cleared: u32,
+}
+
+impl Guideline {
+    pub fn new() -> Guideline {
+        Guideline {
+            points: 0,
+            lines_cleared: 0,
+        }
+    }
+}
+
+#[typetag::serde]
+impl System for Guideline {
+    fn lines_cleared(&mut self, clear: &ClearEvent) -> u32 {
+        let level = clear.level as u32;
+        let mut points = match clear.tspin {
+            TSpin::None => match clear.lines {
+                0 => 0,
+                1 => 100,
+                2 => 300,
+                3 => 500,
+                _ => 800,
+            },
+            TSpin::Mini => match clear.lines {
+                0 => 100,
+                1 => 200,
+                _ => 400,
+            },
+            TSpin::Full => match clear.lines {
+                0 => 400,
+                1 => 800,
+                2 => 1200,
+                _ => 1600,
+            },
+        } * level;
+
+        if clear.back_to_back {
+            points = points * 3 / 2;
+        }
+
+        if clear.combo > 1 {
+            points += 50 * (clear.combo - 1) * level;
+        }
+
+        if clear.perfect_clear {
+            points += match clear.lines {
+                1 => 800,
+                2 => 1200,
+                3 => 1800,
+                _ if clear.back_to_back => 3200,
+                _ => 2000,
+            } * level;
+        }
+
+        self.points += points;
+        self.lines_cleared += clear.lines as u32;
+
+        points
+    }
+
+    fn dropped(&mut self, rows: u32, hard: bool) -> u32 {
+        let points = if hard { 2 * rows } else { rows };
+        self.points += points;
+
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear(lines: u8, tspin: TSpin) -> ClearEvent {
+        ClearEvent {
+            level: 2,
+            lines,
+            tspin,
+            back_to_back: false,
+            combo: 1,
+            perfect_clear: false,
+        }
+    }
+
+    #[test]
+    fn test_guideline_tspins_and_drops() {
+        let mut guideline = Guideline::new();
+
+        assert_eq!(guideline.lines_cleared(&clear(1, TSpin::Full)), 1600);
+        assert_eq!(guideline.lines_cleared(&clear(2, TSpin::Full)), 2400);
+        assert_eq!(guideline.lines_cleared(&clear(3, TSpin::Full)), 3200);
+        assert_eq!(guideline.lines_cleared(&clear(1, TSpin::Mini)), 400);
+        assert_eq!(guideline.dropped(10, false), 10);
+        assert_eq!(guideline.dropped(10, true), 20);
+        assert_eq!(guideline.points, 7630);
+    }
+
+    #[test]
+    fn test_guideline_back_to_back_and_combo_bonuses() {
+        let mut guideline = Guideline::new();
+
+        let mut tetris = clear(4, TSpin::None);
+        tetris.back_to_back = true;
+        assert_eq!(guideline.lines_cleared(&tetris), 2400);
+
+        let mut tspin_double = clear(2, TSpin::Full);
+        tspin_double.back_to_back = true;
+        tspin_double.combo = 3;
+        assert_eq!(guideline.lines_cleared(&tspin_double), 3600 + 200);
+
+        let mut single = clear(1, TSpin::None);
+        single.combo = 4;
+        assert_eq!(guideline.lines_cleared(&single), 200 + 300);
+    }
+}
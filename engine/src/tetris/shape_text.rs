@@ -0,0 +1,222 @@
+// The text format of tetrominos.txt, a name (with an optional #rrggbb colour) and the
+// forms of the piece side by side between braces:
+//
+// Snake #64b452 {
+// .██.   .█..   ....   █...
+// ██..   .██.   .██.   ██..
+// ....   ..█.   ██..   .█..
+// ....   ....   ....   ....
+// }
+//
+// The four forms are the rotations 0, R, 2 and L. A piece with a single form gets the
+// other three by rotating it clockwise.
+//
+// Only uses std, the engine build script includes it to generate the tetrominos.
+
+const FILLED: char = '█';
+const EMPTY: char = '.';
+
+// A form as rows of cells from top to bottom, 1 is filled.
+pub type Rows = Vec<Vec<u8>>;
+
+pub struct Definition {
+    pub name: String,
+    pub colour: Option<(u8, u8, u8)>,
+    // Always the four rotations, each form is as wide as it is high.
+    pub forms: [Rows; 4],
+    // The line of the piece name, for errors about the whole piece.
+    pub line: usize,
+}
+
+// The name, colour, line, line of the first row and rows of each form of the piece
+// being parsed. The forms are side by side, so they all start on the first row.
+type PartialPiece = (String, Option<(u8, u8, u8)>, usize, usize, Vec<Rows>);
+
+// Errors start with the line they are about, "line 3: ...".
+pub fn parse(text: &str) -> Result<Vec<Definition>, String> {
+    let mut definitions = vec![];
+    let mut piece: Option<PartialPiece> = None;
+
+    for (idx, line) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = line.trim();
+        let err = |e: &str| format!("line {}: {}", line_no, e);
+
+        match piece.take() {
+            None if line.is_empty() => (),
+            None => {
+                let Some(header) = line.strip_suffix('{') else {
+                    return Err(err("expected a piece name followed by {"));
+                };
+                let mut words = header.split_whitespace();
+                let Some(name) = words.next() else {
+                    return Err(err("the piece has no name"));
+                };
+                let colour = match words.next() {
+                    Some(hex) => Some(parse_colour(hex).ok_or_else(|| err("bad colour"))?),
+                    None => None,
+                };
+                piece = Some((name.to_string(), colour, line_no, line_no, vec![]));
+            }
+            Some((name, colour, name_line, rows_line, forms)) if line == "}" => {
+                // Errors about the forms point at their rows.
+                let forms = build_forms(forms)
+                    .map_err(|e| format!("line {}: {}: {}", rows_line, name, e))?;
+                definitions.push(Definition {
+                    name,
+                    colour,
+                    forms,
+                    line: name_line,
+                });
+            }
+            Some((name, colour, name_line, mut rows_line, mut forms)) => {
+                let row: Vec<&str> = line.split_whitespace().collect();
+                if forms.is_empty() {
+                    rows_line = line_no;
+                    if row.len() != 1 && row.len() != 4 {
+                        return Err(err("a piece has 1 or 4 forms"));
+                    }
+                    forms = vec![vec![]; row.len()];
+                }
+                if row.len() != forms.len() {
+                    return Err(err("every line needs a row of each form"));
+                }
+
+                for (form, cells) in forms.iter_mut().zip(row) {
+                    let cells = cells
+                        .chars()
+                        .map(|c| match c {
+                            FILLED => Ok(1),
+                            EMPTY => Ok(0),
+                            _ => Err(err(&format!("unexpected {:?}, cells are █ or .", c))),
+                        })
+                        .collect::<Result<Vec<u8>, String>>()?;
+                    if form.first().is_some_and(|first| first.len() != cells.len()) {
+                        return Err(err("every row of a form needs the same number of cells"));
+                    }
+                    form.push(cells);
+                }
+                piece = Some((name, colour, name_line, rows_line, forms));
+            }
+        }
+    }
+
+    if let Some((name, _, name_line, _, _)) = piece {
+        return Err(format!(
+            "line {}: {} is missing its closing }}",
+            name_line, name
+        ));
+    }
+
+    Ok(definitions)
+}
+
+fn build_forms(forms: Vec<Rows>) -> Result<[Rows; 4], String> {
+    for form in forms.iter() {
+        if form.is_empty() || form.len() != form[0].len() {
+            return Err("forms must be as wide as they are high".to_string());
+        }
+    }
+
+    let forms: [Rows; 4] = if forms.len() == 1 {
+        let r = rotated(&forms[0]);
+        let two = rotated(&r);
+        let l = rotated(&two);
+        [forms[0].clone(), r, two, l]
+    } else {
+        forms
+            .try_into()
+            .map_err(|_| "a piece has 1 or 4 forms".to_string())?
+    };
+
+    let cell_count = count_cells(&forms[0]);
+    if cell_count == 0 {
+        return Err("the piece has no cells".to_string());
+    }
+    for (idx, form) in forms.iter().enumerate().skip(1) {
+        let count = count_cells(form);
+        if count != cell_count {
+            return Err(format!(
+                "form {} has {} cells but form 1 has {}",
+                idx + 1,
+                count,
+                cell_count
+            ));
+        }
+        // Where the form sits in its box is up to the rotation system, not its shape.
+        if cells(form) != cells(&rotated(&forms[idx - 1])) {
+            return Err(format!(
+                "form {} is not form {} turned clockwise",
+                idx + 1,
+                idx
+            ));
+        }
+    }
+
+    Ok(forms)
+}
+
+// The form turned a quarter clockwise.
+fn rotated(form: &Rows) -> Rows {
+    let size = form.len();
+    (0..size)
+        .map(|row| (0..size).map(|col| form[size - 1 - col][row]).collect())
+        .collect()
+}
+
+fn count_cells(form: &Rows) -> usize {
+    form.iter().flatten().filter(|cell| **cell != 0).count()
+}
+
+// The filled cells relative to the top left filled row and column.
+fn cells(form: &Rows) -> Vec<(usize, usize)> {
+    let filled: Vec<(usize, usize)> = form
+        .iter()
+        .enumerate()
+        .flat_map(|(row, cols)| {
+            cols.iter()
+                .enumerate()
+                .filter(|(_, cell)| **cell != 0)
+                .map(move |(col, _)| (row, col))
+        })
+        .collect();
+    let top = filled.iter().map(|(row, _)| *row).min().unwrap_or(0);
+    let left = filled.iter().map(|(_, col)| *col).min().unwrap_or(0);
+
+    filled
+        .iter()
+        .map(|(row, col)| (row - top, col - left))
+        .collect()
+}
+
+fn parse_colour(hex: &str) -> Option<(u8, u8, u8)> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |at: usize| u8::from_str_radix(hex.get(at..at + 2)?, 16).ok();
+
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forms_must_be_rotations_of_each_other() {
+        let twisted =
+            "Twisted {\n.█.   .█.   ...   .█.\n███   .██   ███   ██.\n...   .█.   .█.   █..\n}";
+        assert_eq!(
+            parse(twisted).err(),
+            Some("line 2: Twisted: form 4 is not form 3 turned clockwise".to_string())
+        );
+
+        // The line is the first row of the forms, after the pieces before it.
+        let uneven = "Square {\n██\n██\n}\n\nUneven {\n.█.   .█.   ...   .█.\n███   .██   ███   ██.\n...   .█.   .██   .█.\n}";
+        assert_eq!(
+            parse(uneven).err(),
+            Some("line 7: Uneven: form 3 has 5 cells but form 1 has 4".to_string())
+        );
+    }
+}
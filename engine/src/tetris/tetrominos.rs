@@ -0,0 +1,107 @@
+use std::ops;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Kind {
+    Stick,
+    Seven,
+    Hook,
+    Square,
+    Snake,
+    Pyramid,
+    Zig,
+    // A piece of a custom piece set, by its index in the set, see polyominos.
+    Custom(u8),
+}
+
+// The largest bounding box of a piece, pentominos need 5x5.
+pub const MAX_FORM_SIZE: usize = 5;
+
+// A piece in one of its rotations, the filled (1) cells of its size x size bounding box.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Form {
+    size: usize,
+    cells: [[u8; MAX_FORM_SIZE]; MAX_FORM_SIZE],
+}
+
+impl Form {
+    pub const fn new<const N: usize>(rows: [[u8; N]; N]) -> Form {
+        assert!(N <= MAX_FORM_SIZE, "form is larger than MAX_FORM_SIZE");
+
+        let mut cells = [[0; MAX_FORM_SIZE]; MAX_FORM_SIZE];
+        let mut row = 0;
+        while row < N {
+            let mut col = 0;
+            while col < N {
+                cells[row][col] = rows[row][col];
+                col += 1;
+            }
+            row += 1;
+        }
+
+        Form { size: N, cells }
+    }
+
+    pub fn from_rows(rows: &[Vec<u8>]) -> Result<Form, String> {
+        let size = rows.len();
+        if size == 0 || size > MAX_FORM_SIZE {
+            return Err(format!("forms can be 1 to {} cells wide", MAX_FORM_SIZE));
+        }
+
+        let mut cells = [[0; MAX_FORM_SIZE]; MAX_FORM_SIZE];
+        for (row, cols) in rows.iter().enumerate() {
+            if cols.len() != size {
+                return Err("forms must be as wide as they are high".to_string());
+            }
+            cells[row][..size].copy_from_slice(cols);
+        }
+
+        Ok(Form { size, cells })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // The rows of the form, from top to bottom.
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        self.cells[..self.size].iter().map(|row| &row[..self.size])
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.iter().flatten().filter(|cell| **cell != 0).count()
+    }
+}
+
+impl ops::Index<usize> for Form {
+    type Output = [u8];
+
+    fn index(&self, row: usize) -> &[u8] {
+        &self.cells[row][..self.size]
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tetromino {
+    pub kind: Kind,
+    pub forms: [Form; 4],
+}
+
+// STICK, SEVEN, HOOK, SQUARE, SNAKE, PYRAMID and ZIG, generated from tetrominos.txt by
+// the build script.
+include!(concat!(env!("OUT_DIR"), "/tetrominos.rs"));
+
+// The built in tetromino of a kind, custom kinds are defined by the game's piece set.
+pub fn from_kind(k: Kind) -> Option<&'static Tetromino> {
+    match k {
+        Kind::Stick => Some(&STICK),
+        Kind::Seven => Some(&SEVEN),
+        Kind::Hook => Some(&HOOK),
+        Kind::Square => Some(&SQUARE),
+        Kind::Snake => Some(&SNAKE),
+        Kind::Pyramid => Some(&PYRAMID),
+        Kind::Zig => Some(&ZIG),
+        Kind::Custom(_) => None,
+    }
+}
@@ -0,0 +1,95 @@
+use crate::tetris::game;
+
+// Two games played side by side, the garbage each player sends rises in the other
+// player's well. The match ends when a player tops out, the other player wins.
+pub struct Match {
+    pub games: [game::Game; 2],
+    // The garbage each player sent on the last tick.
+    pub sent: [u32; 2],
+}
+
+impl Match {
+    pub fn new(games: [game::Game; 2]) -> Match {
+        Match {
+            games,
+            sent: [0; 2],
+        }
+    }
+
+    // Simulates a tick of both games and then exchanges the garbage they sent.
+    pub fn tick(&mut self) -> usize {
+        let ticks = self.games[0].tick();
+        self.games[1].tick();
+
+        self.sent = [self.games[0].take_attack(), self.games[1].take_attack()];
+        self.games[0].receive_garbage(self.sent[1]);
+        self.games[1].receive_garbage(self.sent[0]);
+
+        for player in 0..2 {
+            if self.games[player].is_gameover() {
+                self.games[1 - player].end_in_victory();
+            }
+        }
+
+        ticks
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.games.iter().any(|g| g.is_finished())
+    }
+
+    // The index of the winning player, there is none while playing or when both players
+    // topped out on the same tick.
+    pub fn winner(&self) -> Option<usize> {
+        self.games.iter().position(|g| g.is_victory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetris::actions;
+    use crate::tetris::attack;
+    use crate::tetris::playfield;
+    use crate::tetris::recordings;
+    use crate::tetris::rules;
+    use crate::tetris::tetrominos;
+
+    #[test]
+    fn test_line_clears_send_garbage_to_the_opponent() {
+        let mut rules = rules::Rules::new();
+        rules.attack_table(attack::AttackTable::Custom(vec![1, 2, 3, 4]));
+        rules.action_cooldown(rules::ActionCooldown::Each(0));
+        let mut m = Match::new([
+            game::Game::new(4_188, rules.clone(), 1, None).unwrap(),
+            game::Game::new(4_188, rules, 2, None).unwrap(),
+        ]);
+
+        let pf = &mut m.games[0].play_field;
+        let bottom_row = pf.rows + 3;
+        for col in 3..pf.cols + 3 {
+            pf.matrix[bottom_row][col] = playfield::Location::Filled(tetrominos::Kind::Stick);
+        }
+
+        m.tick();
+        for player in 0..2 {
+            m.games[player].queue_action(actions::Action::Drop).unwrap();
+        }
+        // Player 2 drops pieces until the garbage has risen into the well.
+        for _ in 0..1_000 {
+            m.tick();
+            if m.games[1].play_field.garbage_rows() > 0 {
+                break;
+            }
+            let _ = m.games[1].queue_action(actions::Action::Drop);
+        }
+
+        assert_eq!(m.games[1].play_field.garbage_rows(), 1);
+        assert!(m.games[1]
+            .recording
+            .events
+            .iter()
+            .any(|ev| ev.kind == recordings::EventKind::GarbageReceived(1)));
+        assert_eq!(m.winner(), None);
+    }
+}
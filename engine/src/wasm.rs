@@ -0,0 +1,183 @@
+use crate::recording_file;
+use crate::replays;
+use crate::tetris::game;
+use crate::tetris::playfield;
+use crate::tetris::tetrominos;
+
+use wasm_bindgen::prelude::*;
+
+// The same tick rate the game client records with.
+const TICK_RATE_US: u64 = 4_188;
+
+// Plays back a recording one tick at a time so a web page can draw it.
+//
+// Matrix cells are numbered 0 for empty, 1 for the edge of the well and 2 + the
+// tetrominos::Kind (in declaration order) for filled cells and 9 for garbage. Pieces
+// of a custom piece set are 10 + their index in the set.
+#[wasm_bindgen]
+pub struct ReplayViewer {
+    game: game::Game,
+    replay: replays::Replay,
+    replay_action_index: usize,
+    ticks: usize,
+}
+
+fn location_cell(location: playfield::Location) -> u8 {
+    match location {
+        playfield::Location::Empty => 0,
+        playfield::Location::Edge => 1,
+        playfield::Location::Filled(kind) => match kind {
+            tetrominos::Kind::Stick => 2,
+            tetrominos::Kind::Seven => 3,
+            tetrominos::Kind::Hook => 4,
+            tetrominos::Kind::Square => 5,
+            tetrominos::Kind::Snake => 6,
+            tetrominos::Kind::Pyramid => 7,
+            tetrominos::Kind::Zig => 8,
+            tetrominos::Kind::Custom(idx) => idx.saturating_add(10),
+        },
+        playfield::Location::Garbage => 9,
+    }
+}
+
+#[wasm_bindgen]
+impl ReplayViewer {
+    // Loads a recording saved by the game client (any version).
+    #[wasm_bindgen(constructor)]
+    pub fn new(json: &str) -> Result<ReplayViewer, String> {
+        let recording_file = recording_file::RecordingFile::from_json(json)?;
+        let (game, replay) = replays::load_game(TICK_RATE_US, recording_file)?;
+
+        Ok(ReplayViewer {
+            game,
+            replay,
+            replay_action_index: 0,
+            ticks: 0,
+        })
+    }
+
+    // Simulates up to n ticks (stopping when the game ends) and returns the current tick.
+    pub fn step(&mut self, n: u32) -> u32 {
+        for _ in 0..n {
+            if self.game.is_finished() {
+                break;
+            }
+            self.replay_action_index =
+                self.replay
+                    .queue_actions(&mut self.game, self.replay_action_index, self.ticks);
+            self.ticks = self.game.tick();
+        }
+        self.ticks as u32
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.ticks as u32
+    }
+
+    pub fn matrix_rows(&self) -> u32 {
+        self.game.play_field.matrix.len() as u32
+    }
+
+    pub fn matrix_cols(&self) -> u32 {
+        self.game.play_field.matrix[0].len() as u32
+    }
+
+    // The playfield matrix (including the padding around the well) in row major order.
+    pub fn matrix(&self) -> Vec<u8> {
+        self.game
+            .play_field
+            .matrix
+            .iter()
+            .flatten()
+            .map(|l| location_cell(*l))
+            .collect()
+    }
+
+    pub fn has_piece(&self) -> bool {
+        self.game.is_piece_active()
+    }
+
+    pub fn piece_x(&self) -> u16 {
+        self.game.piece.x
+    }
+
+    pub fn piece_y(&self) -> u16 {
+        self.game.piece.y
+    }
+
+    // The piece's cell number, same as a filled matrix cell.
+    pub fn piece_cell(&self) -> u8 {
+        location_cell(playfield::Location::Filled(self.game.piece.tetromino))
+    }
+
+    // The form of the piece in its current rotation in row major order, 1 is filled. It
+    // has piece_form_size rows of piece_form_size cells.
+    pub fn piece_form(&self) -> Vec<u8> {
+        self.game.piece_form().iter().flatten().copied().collect()
+    }
+
+    // 4 for the tetrominos, custom pieces can be up to tetrominos::MAX_FORM_SIZE.
+    pub fn piece_form_size(&self) -> usize {
+        self.game.piece_form().size()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.game.score_points()
+    }
+
+    pub fn lines_cleared(&self) -> u32 {
+        self.game.score_lines_cleared()
+    }
+
+    pub fn level(&self) -> u8 {
+        self.game.level
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.game.is_finished()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetris::actions;
+    use crate::tetris::rules;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn recording_json() -> (String, u32) {
+        let mut gm = game::Game::new(TICK_RATE_US, rules::Rules::new(), 7, None).unwrap();
+        while !gm.is_finished() {
+            let _ = gm.queue_action(actions::Action::Drop);
+            gm.tick();
+        }
+
+        let rf = recording_file::RecordingFile::new(
+            2,
+            rules::Rules::new(),
+            gm.recording.clone(),
+            Some(7),
+            gm.score_points(),
+            gm.score_lines_cleared(),
+        );
+        (serde_json::to_string(&rf).unwrap(), gm.score_points())
+    }
+
+    #[test]
+    fn test_replay_viewer_plays_recording_to_game_over() {
+        let (json, final_score) = recording_json();
+        let mut viewer = ReplayViewer::new(&json).unwrap();
+        assert_eq!(
+            viewer.matrix().len() as u32,
+            viewer.matrix_rows() * viewer.matrix_cols()
+        );
+
+        viewer.step(100_000);
+
+        assert!(viewer.is_finished());
+        assert_eq!(viewer.score(), final_score);
+        assert!(viewer.matrix().iter().any(|c| *c >= 2));
+    }
+}
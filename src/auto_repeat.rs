@@ -0,0 +1,165 @@
+use tetris_engine::tetris::actions;
+use tetris_engine::tetris::gravity;
+
+struct Held {
+    action: actions::Action,
+    held_us: u64,
+    next_at_us: u64,
+}
+
+impl Held {
+    fn new(action: actions::Action) -> Held {
+        Held {
+            action,
+            held_us: 0,
+            next_at_us: 0,
+        }
+    }
+}
+
+// Delayed auto shift (DAS) and auto repeat rate (ARR) for held movement inputs.
+// Held inputs are tracked from key/button down until up and played back once per game
+// tick, so keyboards and controllers repeat the same way regardless of OS key repeat.
+pub struct AutoRepeat {
+    das_us: u64,
+    arr_us: u64,
+    soft_drop_factor: u32,
+    // Left and right, the most recently pressed direction wins.
+    shift: Vec<Held>,
+    soft_drop: Option<Held>,
+}
+
+impl AutoRepeat {
+    pub fn new(das_ms: u32, arr_ms: u32, soft_drop_factor: u32) -> AutoRepeat {
+        AutoRepeat {
+            das_us: das_ms as u64 * 1_000,
+            arr_us: arr_ms as u64 * 1_000,
+            soft_drop_factor: soft_drop_factor.max(1),
+            shift: vec![],
+            soft_drop: None,
+        }
+    }
+
+    pub fn press(&mut self, action: actions::Action) {
+        match action {
+            actions::Action::MoveLeft | actions::Action::MoveRight => {
+                self.shift.retain(|h| h.action != action);
+                self.shift.push(Held::new(action));
+            }
+            actions::Action::MoveDown if self.soft_drop.is_none() => {
+                self.soft_drop = Some(Held::new(action))
+            }
+            _ => (),
+        }
+    }
+
+    pub fn release(&mut self, action: actions::Action) {
+        match action {
+            actions::Action::MoveLeft | actions::Action::MoveRight => {
+                self.shift.retain(|h| h.action != action);
+            }
+            actions::Action::MoveDown => self.soft_drop = None,
+            _ => (),
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        self.shift.clear();
+        self.soft_drop = None;
+    }
+
+    // Called once per game tick, returns the actions that fire on this tick.
+    // Shifting fires on press, again after DAS and then every ARR (every tick when ARR
+    // is 0). Soft drop fires on press and then soft_drop_factor times faster than gravity.
+    pub fn tick(&mut self, tick_us: u64, gravity: u32) -> Vec<actions::Action> {
+        let mut fired = vec![];
+
+        if let Some(held) = self.shift.last_mut() {
+            if held.held_us >= held.next_at_us {
+                fired.push(held.action);
+                held.next_at_us = if held.next_at_us == 0 {
+                    self.das_us.max(1)
+                } else {
+                    held.next_at_us + self.arr_us
+                };
+            }
+            held.held_us += tick_us;
+        }
+
+        if let Some(held) = self.soft_drop.as_mut() {
+            if held.held_us >= held.next_at_us {
+                fired.push(held.action);
+                let interval = tick_us * gravity::ONE_G as u64
+                    / (gravity.max(1) as u64 * self.soft_drop_factor as u64);
+                held.next_at_us += interval.max(1);
+            }
+            held.held_us += tick_us;
+        }
+
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tetris_engine::tetris::game;
+    use tetris_engine::tetris::recordings;
+
+    #[test]
+    fn test_shift_fires_on_press_then_after_das_and_every_arr() {
+        let mut ar = AutoRepeat::new(10, 2, 1);
+        ar.press(actions::Action::MoveLeft);
+
+        let fired_at: Vec<u64> = (0..16)
+            .filter(|_| !ar.tick(1_000, gravity::ONE_G).is_empty())
+            .collect();
+
+        assert_eq!(fired_at, vec![0, 10, 12, 14]);
+    }
+
+    #[test]
+    fn test_last_pressed_direction_wins() {
+        let mut ar = AutoRepeat::new(10, 2, 1);
+        ar.press(actions::Action::MoveLeft);
+        ar.press(actions::Action::MoveRight);
+        assert_eq!(ar.tick(1_000, 0), vec![actions::Action::MoveRight]);
+
+        ar.release(actions::Action::MoveRight);
+        assert_eq!(ar.tick(1_000, 0), vec![actions::Action::MoveLeft]);
+    }
+
+    #[test]
+    fn test_default_rules_accept_every_repeat() {
+        let gm_rules = crate::default_rules();
+        let mut gm = game::Game::new(crate::TICK_RATE_US, gm_rules, 1, None).unwrap();
+        // The default DAS 167ms and ARR 33ms, at ~4.2ms per tick.
+        let mut ar = AutoRepeat::new(167, 33, 20);
+        ar.press(actions::Action::MoveLeft);
+        gm.tick();
+
+        let mut queued_at = vec![];
+        for tick in 0..100 {
+            for action in ar.tick(crate::TICK_RATE_US, gm.gravity) {
+                gm.queue_action(action).unwrap();
+                queued_at.push(tick);
+            }
+            gm.tick();
+        }
+
+        // Fires on press, after DAS (40 ticks) and then every ARR (8 ticks).
+        assert_eq!(queued_at, vec![0, 40, 48, 56, 64, 72, 80, 88, 96]);
+        let moves = gm
+            .recording
+            .events
+            .iter()
+            .filter(|ev| {
+                matches!(
+                    ev.kind,
+                    recordings::EventKind::Action(actions::Action::MoveLeft)
+                )
+            })
+            .count();
+        assert_eq!(moves, queued_at.len());
+    }
+}
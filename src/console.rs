@@ -1,7 +1,7 @@
 use crate::actions;
 use crate::assets;
 use crate::graphics;
-use crate::tetris::tetrominos;
+use tetris_engine::tetris::tetrominos;
 
 use sdl2::event;
 use sdl2::keyboard;
@@ -25,7 +25,9 @@ impl ConsoleBlock {
                 40
             }
             ConsoleBlock::Tetromino(kind) => {
-                let tetro = tetrominos::from_kind(*kind);
+                let Some(tetro) = tetrominos::from_kind(*kind) else {
+                    return 0;
+                };
 
                 let cell_size = 32;
                 for i in 0..4 {
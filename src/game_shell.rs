@@ -3,14 +3,17 @@ use std::time;
 
 use crate::actions;
 use crate::assets;
+use crate::auto_repeat;
 use crate::graphics;
 use crate::preferences;
-use crate::replays;
-use crate::tetris;
-use crate::tetris::game;
-use crate::tetris::playfield;
-use crate::tetris::recordings;
-use crate::tetris::tetrominos;
+use tetris_engine::replays;
+use tetris_engine::tetris;
+use tetris_engine::tetris::events;
+use tetris_engine::tetris::game;
+use tetris_engine::tetris::playfield;
+use tetris_engine::tetris::recordings;
+use tetris_engine::tetris::scoring;
+use tetris_engine::tetris::tetrominos;
 
 use sdl2::controller;
 use sdl2::event;
@@ -22,6 +25,9 @@ use sdl2::rwops;
 use sdl2::ttf;
 use sdl2::video;
 
+// How long (in game ticks) the name of the last line clear stays on screen.
+const CLEAR_TEXT_TICKS: usize = 480;
+
 #[derive(PartialEq)]
 enum Mode {
     Tetris,
@@ -37,12 +43,16 @@ pub struct GameShell<'ttf, 'rwops> {
     mode: Mode,
     replay: Option<replays::Replay>,
     replay_action_index: usize,
+    // The name of the last line clear and the tick it was cleared on.
+    clear_text: Option<(String, usize)>,
 
     score_label_font: ttf::Font<'ttf, 'rwops>,
     score_value_font: ttf::Font<'ttf, 'rwops>,
 
     keymap: collections::HashMap<keyboard::Keycode, actions::Action>,
+    auto_repeat: auto_repeat::AutoRepeat,
     controller_x: i16,
+    controller_x_action: Option<tetris::actions::Action>,
     controller_trigger: i16,
 }
 
@@ -51,6 +61,7 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
         initial_game: game::Game,
         registry: &'rwops assets::Registry,
         ttf_context: &'ttf ttf::Sdl2TtfContext,
+        prefs: &preferences::Preferences,
     ) -> Result<GameShell<'ttf, 'rwops>, String> {
         let scp_font_bytes = registry
             .get("fonts/SourceCodePro-Regular.otf")
@@ -77,12 +88,19 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
             mode: Mode::Tetris,
             replay: None,
             replay_action_index: 0,
+            clear_text: None,
 
             score_label_font,
             score_value_font,
 
             keymap,
+            auto_repeat: auto_repeat::AutoRepeat::new(
+                prefs.das_ms,
+                prefs.arr_ms,
+                prefs.soft_drop_factor,
+            ),
             controller_x: 0,
+            controller_x_action: None,
             controller_trigger: 0,
         })
     }
@@ -94,11 +112,24 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
         self.mode = Mode::Replay;
         self.replay = Some(replay);
         self.replay_action_index = 0;
+        self.auto_repeat.release_all();
+        self.clear_text = None;
     }
 
     pub fn load_game(&mut self, game: game::Game) {
+        // A loaded game continues from the tick it was saved on.
+        self.game_ticks = game.ticks();
         self.game = game;
         self.mode = Mode::Tetris;
+        self.replay = None;
+        self.replay_action_index = 0;
+        self.auto_repeat.release_all();
+        self.clear_text = None;
+    }
+
+    pub fn apply_preferences(&mut self, prefs: &preferences::Preferences) {
+        self.auto_repeat =
+            auto_repeat::AutoRepeat::new(prefs.das_ms, prefs.arr_ms, prefs.soft_drop_factor);
     }
 
     pub fn pause(&mut self) {
@@ -113,8 +144,8 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
         self.paused
     }
 
-    pub fn is_gameover(&self) -> bool {
-        self.game.is_gameover()
+    pub fn is_finished(&self) -> bool {
+        self.game.is_finished()
     }
 
     pub fn is_showing_replay(&self) -> bool {
@@ -129,7 +160,7 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
         match self.mode {
             Mode::Replay => Err("Sorry, don't have a recording for you.".to_string()),
             Mode::Tetris => {
-                if self.game.is_gameover() {
+                if self.game.is_finished() {
                     Ok(&self.game.recording)
                 } else {
                     Err("Recording is not available while game is in progress.".to_string())
@@ -147,7 +178,7 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
             return 0;
         }
 
-        if self.game.is_gameover() {
+        if self.game.is_finished() {
             return 0;
         }
 
@@ -164,26 +195,21 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
 
             if self.mode == Mode::Replay {
                 if let Some(ref r) = self.replay {
-                    while self.replay_action_index < r.recording.events.len() - 1
-                        && !matches!(
-                            r.recording.events[self.replay_action_index].kind,
-                            tetris::recordings::EventKind::Action(_)
-                        )
-                    {
-                        self.replay_action_index += 1
-                    }
-                    if let tetris::recordings::EventKind::Action(a) =
-                        r.recording.events[self.replay_action_index].kind
-                    {
-                        if r.recording.events[self.replay_action_index].at <= self.game_ticks {
-                            self.replay_action_index += 1;
-                            let _ = self.game.queue_action(a);
-                        }
-                    }
+                    self.replay_action_index =
+                        r.queue_actions(&mut self.game, self.replay_action_index, self.game_ticks);
+                }
+            } else {
+                for action in self.auto_repeat.tick(dt, self.game.gravity) {
+                    let _ = self.game.queue_action(action);
                 }
             }
 
             self.game_ticks = self.game.tick();
+            for ev in self.game.drain_events() {
+                if let events::EventKind::LinesCleared { rows, tspin } = ev.kind {
+                    self.clear_text = Some((clear_name(rows.len(), tspin), ev.at));
+                }
+            }
         }
         acc_runs
     }
@@ -194,15 +220,17 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
         for event in event_pump.poll_iter() {
             match event {
                 event::Event::Quit { .. } => ui_actions.push(actions::Action::Quit),
+                // Held keys are repeated by auto_repeat, not by the OS.
                 event::Event::KeyDown {
                     keycode: Some(keycode),
+                    repeat: false,
                     ..
                 } => match keycode {
                     //                    keyboard::Keycode::F11 => ui_actions.push(actions::Action::ToggleFullScreen),
                     keyboard::Keycode::Escape => ui_actions.push(actions::Action::MenuShow),
                     keyboard::Keycode::Backquote => ui_actions.push(actions::Action::ConsoleShow),
                     keyboard::Keycode::Space => {
-                        if self.is_gameover() {
+                        if self.is_finished() {
                             ui_actions.push(actions::Action::GameNew)
                         } else {
                             ui_actions.push(actions::Action::TogglePause)
@@ -214,57 +242,102 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
                             ui_actions.push(action.clone());
                         }
 
-                        if !self.paused && !self.game.is_gameover() && self.mode == Mode::Tetris {
+                        if !self.paused && !self.game.is_finished() && self.mode == Mode::Tetris {
                             match keycode {
                                 keyboard::Keycode::Kp7 | keyboard::Keycode::Left => {
-                                    let qr =
-                                        self.game.queue_action(tetris::actions::Action::MoveLeft);
-                                    if let Err(e) = qr {
-                                        println!("{}", e)
-                                    }
+                                    self.auto_repeat.press(tetris::actions::Action::MoveLeft)
                                 }
                                 keyboard::Keycode::Kp9 | keyboard::Keycode::Right => {
-                                    let _ =
-                                        self.game.queue_action(tetris::actions::Action::MoveRight);
+                                    self.auto_repeat.press(tetris::actions::Action::MoveRight)
                                 }
                                 keyboard::Keycode::Kp4 | keyboard::Keycode::Down => {
                                     let _ = self.game.queue_action(tetris::actions::Action::Drop);
                                 }
                                 keyboard::Keycode::Kp5 => {
-                                    let _ =
-                                        self.game.queue_action(tetris::actions::Action::MoveDown);
+                                    self.auto_repeat.press(tetris::actions::Action::MoveDown)
                                 }
                                 keyboard::Keycode::Kp8 | keyboard::Keycode::Up => {
                                     let _ = self.game.queue_action(tetris::actions::Action::Rotate);
                                 }
+                                keyboard::Keycode::Kp6
+                                | keyboard::Keycode::Z
+                                | keyboard::Keycode::LCtrl => {
+                                    let _ =
+                                        self.game.queue_action(tetris::actions::Action::RotateCcw);
+                                }
+                                keyboard::Keycode::Kp2 | keyboard::Keycode::A => {
+                                    let _ =
+                                        self.game.queue_action(tetris::actions::Action::Rotate180);
+                                }
+                                keyboard::Keycode::Kp0
+                                | keyboard::Keycode::C
+                                | keyboard::Keycode::LShift => {
+                                    let _ = self.game.queue_action(tetris::actions::Action::Hold);
+                                }
                                 _ => (),
                             }
                         }
                     }
                 },
+                event::Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => match keycode {
+                    keyboard::Keycode::Kp7 | keyboard::Keycode::Left => {
+                        self.auto_repeat.release(tetris::actions::Action::MoveLeft)
+                    }
+                    keyboard::Keycode::Kp9 | keyboard::Keycode::Right => {
+                        self.auto_repeat.release(tetris::actions::Action::MoveRight)
+                    }
+                    keyboard::Keycode::Kp5 => {
+                        self.auto_repeat.release(tetris::actions::Action::MoveDown)
+                    }
+                    _ => (),
+                },
                 event::Event::ControllerButtonDown { button, .. } => match button {
                     controller::Button::Back => ui_actions.push(actions::Action::MenuShow),
                     controller::Button::Start => {
-                        if self.is_gameover() {
+                        if self.is_finished() {
                             ui_actions.push(actions::Action::GameNew)
                         } else {
                             ui_actions.push(actions::Action::TogglePause)
                         }
                     }
                     controller::Button::X => {
-                        let _ = self.game.queue_action(tetris::actions::Action::MoveLeft);
+                        self.auto_repeat.press(tetris::actions::Action::MoveLeft)
                     }
                     controller::Button::B => {
-                        let _ = self.game.queue_action(tetris::actions::Action::MoveRight);
+                        self.auto_repeat.press(tetris::actions::Action::MoveRight)
                     }
                     controller::Button::A => {
-                        let _ = self.game.queue_action(tetris::actions::Action::MoveDown);
+                        self.auto_repeat.press(tetris::actions::Action::MoveDown)
                     }
                     controller::Button::Y => {
                         let _ = self.game.queue_action(tetris::actions::Action::Rotate);
                     }
+                    controller::Button::LeftShoulder => {
+                        let _ = self.game.queue_action(tetris::actions::Action::RotateCcw);
+                    }
+                    controller::Button::DPadUp => {
+                        let _ = self.game.queue_action(tetris::actions::Action::Rotate180);
+                    }
+                    controller::Button::RightShoulder => {
+                        let _ = self.game.queue_action(tetris::actions::Action::Hold);
+                    }
                     _ => println!("Controller Button = {:?}", button),
                 },
+                event::Event::ControllerButtonUp { button, .. } => match button {
+                    controller::Button::X => {
+                        self.auto_repeat.release(tetris::actions::Action::MoveLeft)
+                    }
+                    controller::Button::B => {
+                        self.auto_repeat.release(tetris::actions::Action::MoveRight)
+                    }
+                    controller::Button::A => {
+                        self.auto_repeat.release(tetris::actions::Action::MoveDown)
+                    }
+                    _ => (),
+                },
                 event::Event::ControllerAxisMotion {
                     axis: controller::Axis::TriggerRight,
                     value: val,
@@ -287,10 +360,22 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
             let _ = self.game.queue_action(tetris::actions::Action::Drop);
         }
 
-        if self.controller_x > 15000 {
-            let _ = self.game.queue_action(tetris::actions::Action::MoveRight);
+        // The stick acts like a held left or right button.
+        let stick_action = if self.controller_x > 15000 {
+            Some(tetris::actions::Action::MoveRight)
         } else if self.controller_x < -15000 {
-            let _ = self.game.queue_action(tetris::actions::Action::MoveLeft);
+            Some(tetris::actions::Action::MoveLeft)
+        } else {
+            None
+        };
+        if stick_action != self.controller_x_action {
+            if let Some(action) = self.controller_x_action {
+                self.auto_repeat.release(action);
+            }
+            if let Some(action) = stick_action {
+                self.auto_repeat.press(action);
+            }
+            self.controller_x_action = stick_action;
         }
 
         ui_actions
@@ -308,80 +393,60 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
         canvas.clear();
 
         let (window_width, window_height) = canvas.window().size();
-        let cell_size: i32 = (window_height / 30) as i32;
+        let cell_size = cell_size_for_playfield(window_width, window_height, &self.game.play_field);
 
-        draw_game(canvas, &self.game, cell_size);
+        let centre_x = window_width as i32 / 2;
+        draw_game(canvas, &self.game, cell_size, centre_x);
+        draw_piece(
+            canvas,
+            &self.game,
+            cell_size,
+            centre_x,
+            &prefs.drop_indicator,
+        );
 
-        let start_x: i32 = (window_width as i32 / 2)
-            - (cell_size * self.game.play_field.cols as i32 / 2)
-            - (3 * cell_size);
+        let start_x = well_start_x(centre_x, cell_size, &self.game.play_field);
         let start_y: i32 = 1;
 
-        if self.game.piece.y < 4 {
-            draw_partial_shape(
-                canvas,
-                *self.game.piece.form(),
-                4 - self.game.piece.y as i16,
-                tetromino_colour(self.game.piece.tetromino),
-                cell_size,
-                start_x + (self.game.piece.x as i32 * cell_size),
-                start_y + (self.game.piece.y as i32 * cell_size),
-            );
-        } else {
+        let preview_x =
+            start_x + (self.game.play_field.cols as i32 * cell_size) + (window_width as i32 / 10);
+        let preview_y = start_y + (window_width as i32 / 10);
+
+        // The piece that spawns next is drawn full size with the rest of the queue
+        // stacked underneath it at half size.
+        let small_cell_size = cell_size / 2;
+        let mut next_y = preview_y;
+        for (idx, kind) in self.game.next_queue.iter().enumerate() {
+            let size = if idx == 0 { cell_size } else { small_cell_size };
             draw_shape(
                 canvas,
-                *self.game.piece.form(),
-                tetromino_colour(self.game.piece.tetromino),
-                cell_size,
-                start_x + (self.game.piece.x as i32 * cell_size),
-                start_y + (self.game.piece.y as i32 * cell_size),
+                self.game.forms(*kind)[0],
+                piece_colour(&self.game, *kind),
+                size,
+                preview_x,
+                next_y,
             );
+            next_y += 3 * size;
         }
 
-        if self.game.drop_distance() > 0 {
-            let drop_row = self.game.piece.y + self.game.drop_distance() as u16 - 1;
-            let form = self.game.piece.form();
-            let mut first_row: usize = 0;
-            if drop_row < 4 {
-                first_row = 4 - drop_row as usize;
-            }
+        if let Some(hold_piece) = self.game.hold_piece {
+            // Greyed out while the hold has already been used for the current piece.
+            let hold_colour = if self.game.can_hold() {
+                piece_colour(&self.game, hold_piece)
+            } else {
+                pixels::Color::RGB(72, 72, 72)
+            };
 
-            if prefs.drop_indicator == preferences::DropIndicatorStyle::Outline {
-                draw_shape_outline_partial(
-                    canvas,
-                    form,
-                    first_row,
-                    tetromino_colour(self.game.piece.tetromino),
-                    cell_size,
-                    start_x + (self.game.piece.x as i32 * cell_size),
-                    start_y
-                        + (self.game.piece.y + self.game.drop_distance() as u16 - 1) as i32
-                            * cell_size,
-                )
-            } else if prefs.drop_indicator == preferences::DropIndicatorStyle::Triangles {
-                draw_shape_triangles(
-                    canvas,
-                    form,
-                    first_row,
-                    tetromino_colour(self.game.piece.tetromino),
-                    cell_size,
-                    start_x + (self.game.piece.x as i32 * cell_size),
-                    start_y
-                        + (self.game.piece.y + self.game.drop_distance() as u16 - 1) as i32
-                            * cell_size,
-                )
-            }
+            draw_shape(
+                canvas,
+                self.game.forms(hold_piece)[0],
+                hold_colour,
+                cell_size,
+                start_x - (3 * cell_size),
+                preview_y,
+            );
         }
 
-        draw_shape(
-            canvas,
-            tetrominos::from_kind(self.game.next_piece).forms[0],
-            tetromino_colour(self.game.next_piece),
-            cell_size,
-            start_x + (self.game.play_field.cols as i32 * cell_size) + (window_width as i32 / 10),
-            start_y + (window_width as i32 / 10),
-        );
-
         let bright_red = pixels::Color::RGBA(255, 0, 0, 255);
         let _blue = pixels::Color::RGBA(30, 100, 240, 255);
         let teal = pixels::Color::RGBA(34, 216, 236, 255);
@@ -392,9 +457,14 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
         let height_third = window_height / 3;
         let vspace = 60;
 
-        let game_time_duration = self.game.time();
-        let game_time_mins = game_time_duration.as_secs() / 60;
-        let game_time_secs = game_time_duration.as_secs() % 60;
+        let lines_remaining = self.game.lines_remaining();
+        let time_remaining = self.game.time_remaining();
+        // Races against a line goal or the clock are timed to the millisecond.
+        let game_time = match time_remaining {
+            Some(remaining) => format_time_precise(remaining),
+            None if lines_remaining.is_some() => format_time_precise(self.game.time()),
+            None => format_time(self.game.time()),
+        };
 
         graphics::render_text(
             canvas,
@@ -420,7 +490,11 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
             label_colour,
             (2 * width_third) as i32 - vspace,
             height_third as i32 + 220,
-            "Lines",
+            if lines_remaining.is_some() {
+                "Lines Left"
+            } else {
+                "Lines"
+            },
         );
 
         graphics::render_text(
@@ -429,7 +503,10 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
             value_colour,
             (2 * width_third) as i32 - vspace,
             height_third as i32 + 260,
-            &format!("{0}", self.game.score_lines_cleared),
+            &format!(
+                "{0}",
+                lines_remaining.unwrap_or(self.game.score_lines_cleared)
+            ),
         );
 
         graphics::render_text(
@@ -456,7 +533,11 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
             label_colour,
             (2 * width_third) as i32 - vspace,
             height_third as i32 + 460,
-            "Game Time",
+            if time_remaining.is_some() {
+                "Time Left"
+            } else {
+                "Game Time"
+            },
         );
 
         graphics::render_text(
@@ -465,10 +546,93 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
             bright_red,
             (2 * width_third) as i32 - vspace,
             height_third as i32 + 500,
-            &format!("{:02}:{:02}", game_time_mins, game_time_secs),
+            &game_time,
+        );
+
+        graphics::render_text(
+            canvas,
+            label_font,
+            label_colour,
+            20,
+            20,
+            &format!("Seed {0}", self.game.seed),
+        );
+
+        if let Some((text, at)) = &self.clear_text {
+            if self.game_ticks < at + CLEAR_TEXT_TICKS {
+                graphics::render_text(
+                    canvas,
+                    label_font,
+                    bright_red,
+                    20,
+                    height_third as i32 + 180,
+                    text,
+                );
+            }
+        }
+
+        if self.game.combo > 1 {
+            graphics::render_text(
+                canvas,
+                label_font,
+                teal,
+                20,
+                height_third as i32 + 220,
+                &format!("Combo {0}", self.game.combo - 1),
+            );
+        }
+
+        if self.game.back_to_back > 1 {
+            graphics::render_text(
+                canvas,
+                label_font,
+                teal,
+                20,
+                height_third as i32 + 260,
+                &format!("Back-to-Back {0}", self.game.back_to_back - 1),
+            );
+        }
+
+        graphics::render_text(
+            canvas,
+            label_font,
+            label_colour,
+            20,
+            height_third as i32 + 300,
+            &format!("Perfect Clears {0}", self.game.perfect_clears),
         );
 
-        if self.game.is_gameover() {
+        if self.game.is_victory() {
+            graphics::render_text_centered(
+                canvas,
+                value_font,
+                teal,
+                (window_width / 2) as i32,
+                50,
+                if time_remaining.is_some() {
+                    "TIME!"
+                } else {
+                    "FINISHED!"
+                },
+            );
+
+            let results = [
+                self.game.rules.mode.to_string(),
+                format!("Score {}", self.game.score_points),
+                format!("Lines {}", self.game.score_lines_cleared),
+                format!("Time {}", format_time_precise(self.game.time())),
+            ];
+            for (i, line) in results.iter().enumerate() {
+                graphics::render_text_centered(
+                    canvas,
+                    label_font,
+                    label_colour,
+                    (window_width / 2) as i32,
+                    110 + 40 * i as i32,
+                    line,
+                );
+            }
+        } else if self.game.is_gameover() {
             graphics::render_text_centered(
                 canvas,
                 value_font,
@@ -476,7 +640,18 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
                 (window_width / 2) as i32,
                 50,
                 "GAME OVER!",
-            )
+            );
+
+            if let Some(reason) = self.game.game_over_reason() {
+                graphics::render_text_centered(
+                    canvas,
+                    label_font,
+                    label_colour,
+                    (window_width / 2) as i32,
+                    110,
+                    &reason.to_string(),
+                );
+            }
         } else if self.paused {
             let x: i32 = (canvas.window().size().0 / 2) as i32;
 
@@ -501,7 +676,30 @@ impl<'ttf, 'rwops> GameShell<'ttf, 'rwops> {
     }
 }
 
-fn tetromino_colour(kind: tetrominos::Kind) -> pixels::Color {
+pub fn format_time(d: time::Duration) -> String {
+    format!("{:02}:{:02}", d.as_secs() / 60, d.as_secs() % 60)
+}
+
+pub fn format_time_precise(d: time::Duration) -> String {
+    format!("{}.{:03}", format_time(d), d.subsec_millis())
+}
+
+fn clear_name(lines: usize, tspin: scoring::TSpin) -> String {
+    let lines_name = match lines {
+        1 => "Single",
+        2 => "Double",
+        3 => "Triple",
+        _ => "Tetris",
+    };
+
+    match tspin {
+        scoring::TSpin::None => lines_name.to_string(),
+        scoring::TSpin::Mini => format!("Mini T-Spin {}", lines_name),
+        scoring::TSpin::Full => format!("T-Spin {}", lines_name),
+    }
+}
+
+pub fn tetromino_colour(kind: tetrominos::Kind) -> pixels::Color {
     match kind {
         tetrominos::Kind::Hook => pixels::Color::RGB(92, 101, 168),
         tetrominos::Kind::Pyramid => pixels::Color::RGB(161, 82, 153),
@@ -510,28 +708,96 @@ fn tetromino_colour(kind: tetrominos::Kind) -> pixels::Color {
         tetrominos::Kind::Square => pixels::Color::RGB(241, 212, 72),
         tetrominos::Kind::Stick => pixels::Color::RGB(99, 196, 234),
         tetrominos::Kind::Zig => pixels::Color::RGB(220, 58, 53),
+        tetrominos::Kind::Custom(idx) => graphics::custom_piece_colour(idx),
+    }
+}
+
+// Custom pieces use the colour from their definition when it has one.
+pub fn piece_colour(game: &game::Game, kind: tetrominos::Kind) -> pixels::Color {
+    match game.polyomino(kind).and_then(|p| p.colour) {
+        Some((r, g, b)) => pixels::Color::RGB(r, g, b),
+        None => tetromino_colour(kind),
     }
 }
 
-impl playfield::Location {
-    fn color(self) -> pixels::Color {
-        match self {
+fn location_colour(game: &game::Game, location: playfield::Location) -> pixels::Color {
+    {
+        match location {
             playfield::Location::Empty => pixels::Color::RGB(0, 0, 0),
             playfield::Location::Edge => pixels::Color::RGB(200, 200, 200),
-            playfield::Location::Filled(k) => match k {
-                tetrominos::Kind::Stick => pixels::Color::RGB(99, 196, 234),
-                tetrominos::Kind::Square => pixels::Color::RGB(241, 212, 72),
-                tetrominos::Kind::Pyramid => pixels::Color::RGB(161, 82, 153),
-                tetrominos::Kind::Seven => pixels::Color::RGB(224, 127, 58),
-                tetrominos::Kind::Snake => pixels::Color::RGB(100, 180, 82),
-                tetrominos::Kind::Hook => pixels::Color::RGB(92, 101, 168),
-                tetrominos::Kind::Zig => pixels::Color::RGB(220, 58, 53),
-            },
+            playfield::Location::Garbage => pixels::Color::RGB(120, 120, 120),
+            playfield::Location::Filled(k) => piece_colour(game, k),
+        }
+    }
+}
+
+// The active piece and its drop indicator.
+pub fn draw_piece(
+    canvas: &mut render::Canvas<video::Window>,
+    game: &game::Game,
+    cell_size: i32,
+    centre_x: i32,
+    drop_indicator: &preferences::DropIndicatorStyle,
+) {
+    let start_x = well_start_x(centre_x, cell_size, &game.play_field);
+    let start_y: i32 = 1;
+
+    if game.is_piece_active() {
+        if game.piece.y < 4 {
+            draw_partial_shape(
+                canvas,
+                *game.piece_form(),
+                4 - game.piece.y as i16,
+                piece_colour(game, game.piece.tetromino),
+                cell_size,
+                start_x + (game.piece.x as i32 * cell_size),
+                start_y + (game.piece.y as i32 * cell_size),
+            );
+        } else {
+            draw_shape(
+                canvas,
+                *game.piece_form(),
+                piece_colour(game, game.piece.tetromino),
+                cell_size,
+                start_x + (game.piece.x as i32 * cell_size),
+                start_y + (game.piece.y as i32 * cell_size),
+            );
+        }
+
+        if game.drop_distance() > 0 {
+            let drop_row = game.piece.y + game.drop_distance() as u16 - 1;
+            let form = game.piece_form();
+            let mut first_row: usize = 0;
+            if drop_row < 4 {
+                first_row = 4 - drop_row as usize;
+            }
+
+            if *drop_indicator == preferences::DropIndicatorStyle::Outline {
+                draw_shape_outline_partial(
+                    canvas,
+                    form,
+                    first_row,
+                    piece_colour(game, game.piece.tetromino),
+                    cell_size,
+                    start_x + (game.piece.x as i32 * cell_size),
+                    start_y + (game.piece.y + game.drop_distance() as u16 - 1) as i32 * cell_size,
+                )
+            } else if *drop_indicator == preferences::DropIndicatorStyle::Triangles {
+                draw_shape_triangles(
+                    canvas,
+                    form,
+                    first_row,
+                    piece_colour(game, game.piece.tetromino),
+                    cell_size,
+                    start_x + (game.piece.x as i32 * cell_size),
+                    start_y + (game.piece.y + game.drop_distance() as u16 - 1) as i32 * cell_size,
+                )
+            }
         }
     }
 }
 
-fn draw_shape(
+pub fn draw_shape(
     canvas: &mut render::Canvas<video::Window>,
     s: playfield::Shape,
     colour: pixels::Color,
@@ -540,8 +806,8 @@ fn draw_shape(
     y: i32,
 ) {
     canvas.set_draw_color(colour);
-    for row in 0..4 {
-        for col in 0..4 {
+    for row in 0..s.size() {
+        for col in 0..s.size() {
             if s[row][col] == 0 {
                 continue;
             }
@@ -565,8 +831,8 @@ fn draw_shape_triangles(
     y: i32,
 ) {
     canvas.set_draw_color(colour);
-    for row in s_first_row..4 {
-        for col in 0..4 {
+    for row in s_first_row..s.size() {
+        for col in 0..s.size() {
             if s[row][col] == 0 {
                 continue;
             }
@@ -598,8 +864,8 @@ fn draw_shape_outline_partial(
     y: i32,
 ) {
     canvas.set_draw_color(colour);
-    for row in s_first_row..4 {
-        for col in 0..4 {
+    for row in s_first_row..s.size() {
+        for col in 0..s.size() {
             if s[row][col] == 0 {
                 continue;
             }
@@ -616,7 +882,7 @@ fn draw_shape_outline_partial(
             }
 
             // draw_right_line
-            if col == 3 || s[row][col + 1] == 0 {
+            if col == s.size() - 1 || s[row][col + 1] == 0 {
                 let _ = canvas.draw_line(
                     rect::Point::new(start_x + size, start_y),
                     rect::Point::new(start_x + size, start_y + size),
@@ -624,7 +890,7 @@ fn draw_shape_outline_partial(
             }
 
             // draw_bottom_line
-            if row == 3 || s[row + 1][col] == 0 {
+            if row == s.size() - 1 || s[row + 1][col] == 0 {
                 let _ = canvas.draw_line(
                     rect::Point::new(start_x, start_y + size),
                     rect::Point::new(start_x + size, start_y + size),
@@ -652,8 +918,8 @@ fn draw_partial_shape(
     y: i32,
 ) {
     canvas.set_draw_color(colour);
-    for row in s_first_row as usize..4 {
-        for col in 0..4 {
+    for row in s_first_row as usize..s.size() {
+        for col in 0..s.size() {
             if s[row][col] == 0 {
                 continue;
             }
@@ -667,15 +933,33 @@ fn draw_partial_shape(
     }
 }
 
+// Cells are sized so the well fits the window height (with room for the rows above it)
+// and the well takes up at most the middle third of the window width.
+pub fn cell_size_for_playfield(
+    window_width: u32,
+    window_height: u32,
+    pf: &playfield::PlayField,
+) -> i32 {
+    let by_height = window_height / (pf.rows as u32 + 8);
+    let by_width = window_width / (3 * pf.cols as u32);
+
+    by_height.min(by_width) as i32
+}
+
+// The x of the left edge of the playfield matrix for a well centred on centre_x.
+pub fn well_start_x(centre_x: i32, size: i32, pf: &playfield::PlayField) -> i32 {
+    // The 3 is the left padding of the playfield.
+    centre_x - (size * pf.cols as i32 / 2) - (3 * size)
+}
+
 fn draw_playfield(
     canvas: &mut render::Canvas<video::Window>,
-    pf: &playfield::PlayField,
+    game: &game::Game,
     size: i32,
+    centre_x: i32,
 ) {
-    let (canvas_width, _) = canvas.window().size();
-
-    // The 3 is the left padding of the playfield.
-    let start_x: i32 = (canvas_width as i32 / 2) - (size * pf.cols as i32 / 2) - (3 * size);
+    let pf = &game.play_field;
+    let start_x = well_start_x(centre_x, size, pf);
     let start_y: i32 = 1;
 
     let well_rows_start = pf.well_y();
@@ -699,7 +983,7 @@ fn draw_playfield(
             if pf.matrix[row][col] == playfield::Location::Empty {
                 continue;
             }
-            canvas.set_draw_color(pf.matrix[row][col].color());
+            canvas.set_draw_color(location_colour(game, pf.matrix[row][col]));
             let _ = canvas.fill_rect(rect::Rect::new(
                 start_x + (col as i32 * size),
                 start_y + (row as i32 * size),
@@ -718,6 +1002,29 @@ fn draw_playfield(
     ));
 }
 
-fn draw_game(canvas: &mut render::Canvas<video::Window>, game: &game::Game, size: i32) {
-    draw_playfield(canvas, &game.play_field, size);
+pub fn draw_game(
+    canvas: &mut render::Canvas<video::Window>,
+    game: &game::Game,
+    size: i32,
+    centre_x: i32,
+) {
+    draw_playfield(canvas, game, size, centre_x);
+
+    // Cleared rows flash white and fade out until the well collapses.
+    if let game::State::ClearingLines(remaining) = game.state() {
+        let pf = &game.play_field;
+        let start_x = well_start_x(centre_x, size, pf);
+        let start_y: i32 = 1;
+
+        let fade = (255 * remaining / game.rules.line_clear_delay.max(1)).min(255) as u8;
+        canvas.set_draw_color(pixels::Color::RGB(fade, fade, fade));
+        for row in game.clearing_rows.iter() {
+            let _ = canvas.fill_rect(rect::Rect::new(
+                start_x + (pf.well_x() as i32 * size),
+                start_y + (*row as i32 * size),
+                (size * pf.cols as i32) as u32,
+                size as u32,
+            ));
+        }
+    }
 }
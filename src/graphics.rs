@@ -1,4 +1,4 @@
-use crate::tetris::tetrominos;
+use tetris_engine::tetris::tetrominos;
 
 use sdl2::pixels;
 use sdl2::rect;
@@ -73,8 +73,8 @@ pub fn render_form(
     y: i32,
 ) {
     canvas.set_draw_color(colour);
-    for row in 0..4 {
-        for col in 0..4 {
+    for row in 0..s.size() {
+        for col in 0..s.size() {
             if s[row][col] == 0 {
                 continue;
             }
@@ -97,5 +97,21 @@ pub fn tetromino_colour(kind: tetrominos::Kind) -> pixels::Color {
         tetrominos::Kind::Square => pixels::Color::RGB(241, 212, 72),
         tetrominos::Kind::Stick => pixels::Color::RGB(99, 196, 234),
         tetrominos::Kind::Zig => pixels::Color::RGB(220, 58, 53),
+        tetrominos::Kind::Custom(idx) => custom_piece_colour(idx),
     }
 }
+
+// For custom pieces defined without a colour.
+pub fn custom_piece_colour(idx: u8) -> pixels::Color {
+    const PALETTE: [(u8, u8, u8); 6] = [
+        (231, 120, 160),
+        (96, 200, 176),
+        (200, 168, 96),
+        (148, 120, 224),
+        (176, 208, 80),
+        (224, 96, 64),
+    ];
+    let (r, g, b) = PALETTE[idx as usize % PALETTE.len()];
+
+    pixels::Color::RGB(r, g, b)
+}
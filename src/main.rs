@@ -3,22 +3,32 @@ use std::env;
 use std::fs;
 use std::io;
 use std::io::Write;
+use std::net;
 use std::time;
 
 mod actions;
 mod assets;
+mod auto_repeat;
 mod console;
 mod game_shell;
 mod graphics;
 mod menu;
 mod preferences;
-mod recording_file;
-mod replays;
-mod tetris;
+mod versus_shell;
 use tetris::game;
+use tetris::gravity;
+use tetris::modes;
+use tetris::polyominos;
+use tetris::randomizers;
+use tetris::recordings;
+use tetris::rotation;
 use tetris::rules;
 use tetris::scoring;
 use tetris::tetrominos;
+use tetris_engine::netplay;
+use tetris_engine::recording_file;
+use tetris_engine::replays;
+use tetris_engine::tetris;
 
 extern crate sdl2;
 use sdl2::controller;
@@ -28,7 +38,10 @@ use sdl2::video;
 
 use serde;
 
-const GAME_VERSION: u32 = 1;
+const GAME_VERSION: u32 = 2;
+const TICK_RATE_US: u64 = 4_188; // microseconds - ~240Hz
+const SERVE_ADDR: &str = "0.0.0.0:7488";
+const INPUT_DELAY_TICKS: usize = 12; // ~50ms
 
 #[rustfmt::skip]
 const ASSET_MANIFEST: [&str; 2] = [
@@ -84,13 +97,38 @@ fn load_preferences_from_file(path: &str) -> Result<preferences::Preferences, St
     Err("Preferences not found".to_string())
 }
 
-fn load_last_game_state() -> Result<game::Game, String> {
-    if let Ok(last_game_state_file) = fs::File::open("last_game_state.json") {
-        let last_game_state_reader = io::BufReader::new(last_game_state_file);
+fn default_rules() -> rules::Rules {
+    let mut game_rules = tetris::rules::Rules::new();
+    game_rules.mode(modes::GameMode::Marathon);
+    game_rules.lock_delay(50);
+    game_rules.scoring_system(scoring::Kind::OriginalSega);
+    // Held moves and soft drop repeat at the DAS/ARR preferences, see auto_repeat.
+    // A cooldown would throttle them below the rate the player picked.
+    game_rules.action_cooldown(rules::ActionCooldown::Each(0));
+    game_rules.rotation_system(rotation::Kind::SuperRotation);
+    game_rules.next_queue_size(5);
+    game_rules.randomizer(randomizers::Kind::SevenBag);
+    game_rules.lock_down(rules::LockDown::Extended(15));
+    game_rules.spawn_delay(0);
+    game_rules.line_clear_delay(48); // 48 ticks = ~200ms
+    game_rules.well_width(10);
+    game_rules.well_height(22);
+    game_rules.gravity_curve(gravity::Curve::Legacy);
+    game_rules.level_goal(rules::LevelGoal::Legacy);
+    // game_rules.lock_delay_on_hard_drop(true);
+
+    game_rules
+}
+
+fn load_rules_from_file(path: &str) -> Result<rules::Rules, String> {
+    let rules_str = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    toml::from_str(&rules_str).map_err(|e| e.to_string())
+}
 
-        let last_game_state: game::Game =
-            serde_json::from_reader(last_game_state_reader).map_err(|e| e.to_string())?;
-        if !last_game_state.is_gameover() {
+fn load_last_game_state() -> Result<game::Game, String> {
+    if let Ok(last_game_state_json) = fs::read_to_string("last_game_state.json") {
+        let last_game_state = game::Game::from_json(&last_game_state_json)?;
+        if !last_game_state.is_finished() {
             return Ok(last_game_state);
         }
     }
@@ -98,16 +136,20 @@ fn load_last_game_state() -> Result<game::Game, String> {
     Err("Previous game state not available.".to_string())
 }
 
+fn load_match_recording(path: &str) -> Result<recording_file::MatchFile, String> {
+    let match_json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    recording_file::MatchFile::from_json(&match_json)
+}
+
 fn load_recording(path: &str) -> Result<recording_file::RecordingFile, String> {
-    let recording_file = fs::File::open(path).map_err(|e| e.to_string())?;
-    let recording_file_reader = io::BufReader::new(recording_file);
-    let recording: recording_file::RecordingFile =
-        serde_json::from_reader(recording_file_reader).map_err(|e| e.to_string())?;
+    let recording_json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let recording = recording_file::RecordingFile::from_json(&recording_json)?;
 
     Ok(recording_file::RecordingFile::new(
         GAME_VERSION,
         recording.rules,
         recording.recording,
+        recording.seed,
         recording.final_score,
         recording.final_lines_cleared,
     ))
@@ -116,15 +158,31 @@ fn load_recording(path: &str) -> Result<recording_file::RecordingFile, String> {
 #[derive(serde::Deserialize, serde::Serialize)]
 struct RecordingStats {
     rules: rules::Rules,
+    seed: Option<u64>,
     score: u32,
     lines_cleared: u32,
+    max_combo: u32,
+    max_back_to_back: u32,
+    perfect_clears: u32,
+    game_over_reason: Option<game::GameOverReason>,
+    mode: modes::GameMode,
+    // Tick and time (at the client tick rate) the mode goal was reached, if it was.
+    finished_at: Option<usize>,
+    finish_time_ms: Option<u64>,
 }
 
 fn main() -> Result<(), String> {
     let args: Vec<String> = env::args().collect();
 
     let mut replay: Option<recording_file::RecordingFile> = None;
+    let mut play_seed: Option<u64> = None;
+    let mut play_mode: Option<modes::GameMode> = None;
+    let mut piece_set: Option<polyominos::PieceSet> = None;
     let mut last_game = None;
+    let mut play_versus = false;
+    let mut versus_replay: Option<recording_file::MatchFile> = None;
+    let mut versus_addr: Option<String> = None;
+    let mut input_delay = INPUT_DELAY_TICKS;
     if args.len() > 1 {
         let cmd = &args[1];
         if cmd == "replay" {
@@ -140,10 +198,40 @@ fn main() -> Result<(), String> {
             if args.len() > 2 {
                 match load_recording(&args[2]) {
                     Ok(recording_file) => {
+                        let mut max_combo = 0;
+                        let mut max_back_to_back = 0;
+                        let mut perfect_clears = 0;
+                        let mut game_over_reason = None;
+                        let mut finished_at = None;
+                        for ev in recording_file.recording.events.iter() {
+                            match ev.kind {
+                                recordings::EventKind::Combo(n) => {
+                                    max_combo = max_combo.max(n.saturating_sub(1))
+                                }
+                                recordings::EventKind::BackToBack(n) => {
+                                    max_back_to_back = max_back_to_back.max(n.saturating_sub(1))
+                                }
+                                recordings::EventKind::PerfectClear => perfect_clears += 1,
+                                recordings::EventKind::GameOver(reason) => {
+                                    game_over_reason = Some(reason)
+                                }
+                                recordings::EventKind::Finished => finished_at = Some(ev.at),
+                                _ => {}
+                            }
+                        }
+
                         let rs = RecordingStats {
+                            mode: recording_file.mode,
                             rules: recording_file.rules,
+                            seed: recording_file.seed,
                             score: recording_file.final_score,
                             lines_cleared: recording_file.final_lines_cleared,
+                            max_combo,
+                            max_back_to_back,
+                            perfect_clears,
+                            game_over_reason,
+                            finished_at,
+                            finish_time_ms: finished_at.map(|at| at as u64 * TICK_RATE_US / 1_000),
                         };
                         if let Ok(rs_string) = serde_json::to_string_pretty(&rs) {
                             println!("{}", rs_string);
@@ -156,8 +244,64 @@ fn main() -> Result<(), String> {
             } else {
                 return Err("Usage: tetris replay-stats <recording path>".to_string());
             }
+        } else if cmd == "seed" {
+            if args.len() > 2 {
+                play_seed = Some(args[2].parse::<u64>().map_err(|e| e.to_string())?);
+            } else {
+                return Err("Usage: tetris seed <seed>".to_string());
+            }
+        } else if cmd == "sprint" {
+            let lines = match args.get(2) {
+                Some(lines) => lines.parse::<u32>().map_err(|e| e.to_string())?,
+                None => 40,
+            };
+            play_mode = Some(modes::GameMode::Sprint(lines));
+        } else if cmd == "ultra" {
+            let secs = match args.get(2) {
+                Some(secs) => secs.parse::<u64>().map_err(|e| e.to_string())?,
+                None => 120,
+            };
+            let ticks = secs * 1_000_000 / TICK_RATE_US;
+            play_mode = Some(modes::GameMode::Ultra(ticks as usize));
+        } else if cmd == "dig" {
+            let rows = match args.get(2) {
+                Some(rows) => rows.parse::<u32>().map_err(|e| e.to_string())?,
+                None => 10,
+            };
+            play_mode = Some(modes::GameMode::Dig(rows));
+        } else if cmd == "pieces" {
+            if args.len() > 2 {
+                let definitions = fs::read_to_string(&args[2]).map_err(|e| e.to_string())?;
+                // Report a bad definition before the window opens.
+                polyominos::parse(&definitions)?;
+                piece_set = Some(polyominos::PieceSet::Custom(definitions));
+            } else {
+                return Err("Usage: tetris pieces <piece set path>".to_string());
+            }
+        } else if cmd == "versus" {
+            play_versus = true;
+        } else if cmd == "serve" {
+            let addr = args.get(2).map_or(SERVE_ADDR, |addr| addr.as_str());
+            let listener = net::TcpListener::bind(addr).map_err(|e| e.to_string())?;
+            println!("Relaying versus matches on {}", addr);
+            return netplay::serve(listener);
+        } else if cmd == "connect" {
+            if args.len() > 2 {
+                versus_addr = Some(args[2].clone());
+                if let Some(ticks) = args.get(3) {
+                    input_delay = ticks.parse::<usize>().map_err(|e| e.to_string())?;
+                }
+            } else {
+                return Err("Usage: tetris connect <relay address> [input delay ticks]".to_string());
+            }
+        } else if cmd == "replay-versus" {
+            if args.len() > 2 {
+                versus_replay = Some(load_match_recording(&args[2])?);
+            } else {
+                return Err("Usage: tetris replay-versus <match recording path>".to_string());
+            }
         } else {
-            return Err("Usage: tetris | tetris replay <recording path> | tetris replay-stats <recording-path>".to_string());
+            return Err("Usage: tetris | tetris replay <recording path> | tetris replay-stats <recording-path> | tetris seed <seed> | tetris sprint [lines] | tetris ultra [seconds] | tetris dig [rows] | tetris pieces <piece set path> | tetris versus | tetris replay-versus <match recording path> | tetris serve [address] | tetris connect <relay address> [input delay ticks]".to_string());
         }
     } else {
         match load_last_game_state() {
@@ -258,7 +402,7 @@ fn main() -> Result<(), String> {
 
     let display_mode = window.display_mode()?;
 
-    let tick_rate = 4_188; // microseconds - ~240Hz
+    let tick_rate = TICK_RATE_US;
     let frame_rate: u64 = (1_000_000 / display_mode.refresh_rate) as u64;
     //          let frame_rate = 8_333; // microseconds - ~120Hz
     eprintln!(
@@ -280,27 +424,31 @@ fn main() -> Result<(), String> {
 
     let _total = 0;
 
-    let mut game_rules = tetris::rules::Rules::new();
-    game_rules.lock_delay(50);
-    game_rules.scoring_system(scoring::Kind::OriginalSega);
-    game_rules.action_cooldown(rules::ActionCooldown::Each(20));
-    // game_rules.action_cooldown(rules::ActionCooldown::Shared(100));
-    // game_rules.lock_delay_on_hard_drop(true);
+    let mut game_rules = default_rules();
+
+    // Training variants (4 wide, 20 x 20 etc) can be set up in rules.toml.
+    if let Ok(rules) = load_rules_from_file("rules.toml") {
+        game_rules = rules;
+    }
+    if let Some(mode) = play_mode {
+        game_rules.mode(mode);
+    }
+    if let Some(set) = piece_set {
+        game_rules.piece_set(set);
+    }
 
     let mut game_shell = game_shell::GameShell::new(
-        game::Game::new(tick_rate, game_rules.clone(), None)?,
+        game::Game::new(tick_rate, game_rules.clone(), game::new_seed(), None)?,
         &registry,
         &ttf_context,
+        &prefs,
     )?;
 
     if let Some(recording_file) = replay {
-        let rp = replays::Replay {
-            recording: recording_file.recording,
-        };
-        let replay_pieces = replays::ReplayPieces::new(&rp);
-        let replay_game =
-            game::Game::new(tick_rate, game_rules.clone(), Some(Box::new(replay_pieces)))?;
+        let (replay_game, rp) = replays::load_game(tick_rate, recording_file)?;
         game_shell.load_replay(replay_game, rp)
+    } else if let Some(seed) = play_seed {
+        game_shell.load_game(game::Game::new(tick_rate, game_rules.clone(), seed, None)?)
     } else if let Some(lg) = last_game {
         game_shell.load_game(lg)
     };
@@ -310,6 +458,37 @@ fn main() -> Result<(), String> {
         ui_layers.hide(UI_LAYER_MENU);
     }
 
+    let new_match = |rules: &rules::Rules| -> Result<[game::Game; 2], String> {
+        Ok([
+            game::Game::new(tick_rate, rules.clone(), game::new_seed(), None)?,
+            game::Game::new(tick_rate, rules.clone(), game::new_seed(), None)?,
+        ])
+    };
+    let controller_ids: Vec<u32> = game_controllers.iter().map(|c| c.instance_id()).collect();
+    let mut versus_shell = versus_shell::VersusShell::new(
+        new_match(&game_rules)?,
+        &registry,
+        &ttf_context,
+        &prefs,
+        &controller_ids,
+    )?;
+    let mut versus_active = play_versus;
+    if let Some(match_file) = versus_replay {
+        versus_shell.load_replay(match_file, tick_rate)?;
+        versus_shell.unpause();
+        versus_active = true;
+        ui_layers.hide(UI_LAYER_MENU);
+    } else if let Some(addr) = versus_addr {
+        println!("Waiting for an opponent at {}...", addr);
+        let seeds = [game::new_seed(), game::new_seed()];
+        let lockstep = netplay::connect(&addr, game_rules.clone(), seeds, input_delay)?;
+        let versus = lockstep.new_match(tick_rate)?;
+        versus_shell.load_net_match(lockstep, versus);
+        versus_shell.unpause();
+        versus_active = true;
+        ui_layers.hide(UI_LAYER_MENU);
+    }
+
     let mut mainloop_iterations: u128 = 0;
     let mut sim_ticks = 0;
     let mut frames = 0;
@@ -338,6 +517,8 @@ fn main() -> Result<(), String> {
                     console.process_events(&mut event_pump)
                 } else if ui_layers.is_showing(UI_LAYER_MENU) {
                     menu.process_events(&mut event_pump)
+                } else if versus_active {
+                    versus_shell.process_events(&mut event_pump)
                 } else {
                     game_shell.process_events(&mut event_pump)
                 }
@@ -346,34 +527,48 @@ fn main() -> Result<(), String> {
             for action in ui_actions.iter() {
                 match action {
                     actions::Action::Quit => break 'main,
-                    actions::Action::PreferencesUpdate(p) => prefs = p.clone(),
+                    actions::Action::PreferencesUpdate(p) => {
+                        prefs = p.clone();
+                        game_shell.apply_preferences(&prefs);
+                    }
+                    actions::Action::Resume if versus_active => {
+                        if versus_shell.is_finished() {
+                            versus_shell.load_match(new_match(&game_rules)?);
+                        }
+                        ui_layers.hide(UI_LAYER_MENU);
+                        versus_shell.unpause();
+                    }
                     actions::Action::Resume => {
-                        if game_shell.is_gameover() {
-                            let new_game = game::Game::new(tick_rate, game_rules.clone(), None)?;
+                        if game_shell.is_finished() {
+                            let new_game = game::Game::new(
+                                tick_rate,
+                                game_rules.clone(),
+                                game::new_seed(),
+                                None,
+                            )?;
                             game_shell.load_game(new_game);
                         }
                         ui_layers.hide(UI_LAYER_MENU);
                         game_shell.unpause();
                     }
+                    actions::Action::GameNew if versus_active => {
+                        versus_shell.load_match(new_match(&game_rules)?);
+                    }
                     actions::Action::GameNew => {
-                        let new_game = game::Game::new(tick_rate, game_rules.clone(), None)?;
+                        let new_game =
+                            game::Game::new(tick_rate, game_rules.clone(), game::new_seed(), None)?;
                         game_shell.load_game(new_game);
                     }
                     actions::Action::ReplayLoad(path) => match load_recording(path) {
                         Ok(recording_file) => {
-                            let replay = replays::Replay {
-                                recording: recording_file.recording,
-                            };
-                            let replay_pieces = replays::ReplayPieces::new(&replay);
-                            let replay_game = game::Game::new(
-                                tick_rate,
-                                recording_file.rules,
-                                Some(Box::new(replay_pieces)),
-                            )?;
-                            game_shell.load_replay(replay_game, replay)
+                            let (replay_game, replay) =
+                                replays::load_game(tick_rate, recording_file)?;
+                            game_shell.load_replay(replay_game, replay);
+                            versus_active = false;
                         }
                         Err(_) => (),
                     },
+                    actions::Action::TogglePause if versus_active => versus_shell.toggle_pause(),
                     actions::Action::TogglePause => game_shell.toggle_pause(),
                     actions::Action::ToggleFullScreen => {
                         if canvas.window().fullscreen_state() == video::FullscreenType::Off {
@@ -389,10 +584,15 @@ fn main() -> Result<(), String> {
                     actions::Action::MenuShow => {
                         ui_layers.show(UI_LAYER_MENU);
                         game_shell.pause();
+                        versus_shell.pause();
                     }
                     actions::Action::MenuHide => {
                         ui_layers.hide(UI_LAYER_MENU);
-                        game_shell.unpause();
+                        if versus_active {
+                            versus_shell.unpause();
+                        } else {
+                            game_shell.unpause();
+                        }
                     }
                     actions::Action::ConsoleShow => ui_layers.show(UI_LAYER_CONSOLE),
                     actions::Action::ConsoleHide => ui_layers.hide(UI_LAYER_CONSOLE),
@@ -414,7 +614,11 @@ fn main() -> Result<(), String> {
                 }
             }
 
-            sim_ticks += game_shell.frame_tick(prev_sim_tick_at, tick_rate);
+            if versus_active {
+                sim_ticks += versus_shell.frame_tick(prev_sim_tick_at, tick_rate);
+            } else {
+                sim_ticks += game_shell.frame_tick(prev_sim_tick_at, tick_rate);
+            }
             prev_sim_tick_at = sim_started_at;
         }
 
@@ -423,7 +627,11 @@ fn main() -> Result<(), String> {
             let render_started_at = time::Instant::now();
 
             prev_render_frame_at = time::Instant::now();
-            game_shell.render(&mut canvas, &prefs);
+            if versus_active {
+                versus_shell.render(&mut canvas, &prefs);
+            } else {
+                game_shell.render(&mut canvas, &prefs);
+            }
 
             let (ww, _) = canvas.window().size();
             graphics::render_text(
@@ -495,12 +703,20 @@ fn main() -> Result<(), String> {
             GAME_VERSION,
             rules,
             (*recording).clone(),
+            Some(game_shell.game().seed),
             final_score,
             final_lines_cleared,
         );
         serde_json::to_writer_pretty(&mut recording_file, &rf).map_err(|e| e.to_string())?;
     }
 
+    if let Ok(match_file) = versus_shell.recording_files(GAME_VERSION) {
+        let mut match_recording_file =
+            fs::File::create("last_match_recording.json").map_err(|e| e.to_string())?;
+        serde_json::to_writer_pretty(&mut match_recording_file, &match_file)
+            .map_err(|e| e.to_string())?;
+    }
+
     if !game_shell.is_showing_replay() {
         let mut last_game_state_file =
             fs::File::create("last_game_state.json").map_err(|e| e.to_string())?;
@@ -8,15 +8,40 @@ pub enum DropIndicatorStyle {
     Triangles = 3,
 }
 
+// Preferences saved before auto repeat existed get the defaults.
+fn default_das_ms() -> u32 {
+    167
+}
+
+fn default_arr_ms() -> u32 {
+    33
+}
+
+fn default_soft_drop_factor() -> u32 {
+    20
+}
+
 #[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Preferences {
     pub drop_indicator: DropIndicatorStyle,
+    // Delayed auto shift, how long left/right must be held before it starts repeating.
+    #[serde(default = "default_das_ms")]
+    pub das_ms: u32,
+    // Auto repeat rate, the time between repeats once DAS has charged (0 is every tick).
+    #[serde(default = "default_arr_ms")]
+    pub arr_ms: u32,
+    // How many times faster than gravity a held soft drop moves the piece down.
+    #[serde(default = "default_soft_drop_factor")]
+    pub soft_drop_factor: u32,
 }
 
 impl Preferences {
     pub fn new() -> Preferences {
         Preferences {
             drop_indicator: DropIndicatorStyle::Outline,
+            das_ms: default_das_ms(),
+            arr_ms: default_arr_ms(),
+            soft_drop_factor: default_soft_drop_factor(),
         }
     }
 }
@@ -1,7 +0,0 @@
-pub mod actions;
-pub mod game;
-pub mod playfield;
-pub mod recordings;
-pub mod rules;
-pub mod scoring;
-pub mod tetrominos;
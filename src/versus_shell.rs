@@ -0,0 +1,531 @@
+use std::time;
+
+use crate::actions;
+use crate::assets;
+use crate::auto_repeat;
+use crate::game_shell;
+use crate::graphics;
+use crate::preferences;
+use tetris_engine::netplay;
+use tetris_engine::recording_file;
+use tetris_engine::replays;
+use tetris_engine::tetris;
+use tetris_engine::tetris::game;
+use tetris_engine::tetris::versus;
+
+use sdl2::controller;
+use sdl2::event;
+use sdl2::keyboard;
+use sdl2::pixels;
+use sdl2::render;
+use sdl2::rwops;
+use sdl2::ttf;
+use sdl2::video;
+
+struct Player {
+    auto_repeat: auto_repeat::AutoRepeat,
+    // The instance id of the game controller the player uses, if they have one.
+    controller_id: Option<u32>,
+    game_ticks: usize,
+    replay: Option<replays::Replay>,
+    replay_action_index: usize,
+}
+
+impl Player {
+    fn new(prefs: &preferences::Preferences, controller_id: Option<u32>) -> Player {
+        Player {
+            auto_repeat: auto_repeat::AutoRepeat::new(
+                prefs.das_ms,
+                prefs.arr_ms,
+                prefs.soft_drop_factor,
+            ),
+            controller_id,
+            game_ticks: 0,
+            replay: None,
+            replay_action_index: 0,
+        }
+    }
+}
+
+// Split screen versus for two players on one machine. Player 1 plays on the left half of
+// the keyboard (or the first game controller), player 2 on the right half and the arrows
+// (or the second game controller). Online the local player can use either.
+pub struct VersusShell<'ttf, 'rwops> {
+    versus: versus::Match,
+    players: [Player; 2],
+    paused: bool,
+    net: Option<netplay::Lockstep>,
+    // Waiting for the opponent's input online, or why the online match ended early.
+    net_waiting: bool,
+    net_error: Option<String>,
+
+    label_font: ttf::Font<'ttf, 'rwops>,
+    value_font: ttf::Font<'ttf, 'rwops>,
+}
+
+fn keyboard_binding(keycode: keyboard::Keycode) -> Option<(usize, tetris::actions::Action)> {
+    match keycode {
+        keyboard::Keycode::A => Some((0, tetris::actions::Action::MoveLeft)),
+        keyboard::Keycode::D => Some((0, tetris::actions::Action::MoveRight)),
+        keyboard::Keycode::S => Some((0, tetris::actions::Action::MoveDown)),
+        keyboard::Keycode::W => Some((0, tetris::actions::Action::Drop)),
+        keyboard::Keycode::Q => Some((0, tetris::actions::Action::RotateCcw)),
+        keyboard::Keycode::E => Some((0, tetris::actions::Action::Rotate)),
+        keyboard::Keycode::LShift => Some((0, tetris::actions::Action::Hold)),
+        keyboard::Keycode::Left => Some((1, tetris::actions::Action::MoveLeft)),
+        keyboard::Keycode::Right => Some((1, tetris::actions::Action::MoveRight)),
+        keyboard::Keycode::Down => Some((1, tetris::actions::Action::MoveDown)),
+        keyboard::Keycode::Up => Some((1, tetris::actions::Action::Drop)),
+        keyboard::Keycode::Period => Some((1, tetris::actions::Action::RotateCcw)),
+        keyboard::Keycode::Slash => Some((1, tetris::actions::Action::Rotate)),
+        keyboard::Keycode::RShift => Some((1, tetris::actions::Action::Hold)),
+        _ => None,
+    }
+}
+
+fn controller_binding(button: controller::Button) -> Option<tetris::actions::Action> {
+    match button {
+        controller::Button::X => Some(tetris::actions::Action::MoveLeft),
+        controller::Button::B => Some(tetris::actions::Action::MoveRight),
+        controller::Button::A => Some(tetris::actions::Action::MoveDown),
+        controller::Button::Y => Some(tetris::actions::Action::Rotate),
+        controller::Button::LeftShoulder => Some(tetris::actions::Action::RotateCcw),
+        controller::Button::DPadUp => Some(tetris::actions::Action::Rotate180),
+        controller::Button::DPadDown => Some(tetris::actions::Action::Drop),
+        controller::Button::RightShoulder => Some(tetris::actions::Action::Hold),
+        _ => None,
+    }
+}
+
+impl<'ttf, 'rwops> VersusShell<'ttf, 'rwops> {
+    pub fn new(
+        games: [game::Game; 2],
+        registry: &'rwops assets::Registry,
+        ttf_context: &'ttf ttf::Sdl2TtfContext,
+        prefs: &preferences::Preferences,
+        controller_ids: &[u32],
+    ) -> Result<VersusShell<'ttf, 'rwops>, String> {
+        let scp_font_bytes = registry
+            .get("fonts/SourceCodePro-Regular.otf")
+            .map_err(|e| e.to_string())?;
+        let ps2_font_bytes = registry
+            .get("fonts/PressStart2P-Regular.ttf")
+            .map_err(|e| e.to_string())?;
+
+        let label_rwops = rwops::RWops::from_bytes(scp_font_bytes)?;
+        let label_font = ttf_context.load_font_from_rwops(label_rwops, 28)?;
+        let value_rwops = rwops::RWops::from_bytes(ps2_font_bytes)?;
+        let mut value_font = ttf_context.load_font_from_rwops(value_rwops, 44)?;
+        value_font.set_style(sdl2::ttf::FontStyle::BOLD);
+
+        Ok(VersusShell {
+            versus: versus::Match::new(games),
+            players: [
+                Player::new(prefs, controller_ids.first().copied()),
+                Player::new(prefs, controller_ids.get(1).copied()),
+            ],
+            paused: true,
+            net: None,
+            net_waiting: false,
+            net_error: None,
+
+            label_font,
+            value_font,
+        })
+    }
+
+    pub fn load_match(&mut self, games: [game::Game; 2]) {
+        self.versus = versus::Match::new(games);
+        self.net = None;
+        self.net_waiting = false;
+        self.net_error = None;
+        for player in self.players.iter_mut() {
+            player.auto_repeat.release_all();
+            player.game_ticks = 0;
+            player.replay = None;
+            player.replay_action_index = 0;
+        }
+    }
+
+    // An online match, created by the lockstep connection (see Lockstep::new_match).
+    pub fn load_net_match(&mut self, lockstep: netplay::Lockstep, versus: versus::Match) {
+        self.load_match(versus.games);
+        self.net = Some(lockstep);
+    }
+
+    // Replays play every recording back on its own, the recorded garbage is received
+    // on the same ticks it was received during the match.
+    pub fn load_replay(
+        &mut self,
+        match_file: recording_file::MatchFile,
+        tick_rate: u64,
+    ) -> Result<(), String> {
+        let mut loaded = vec![];
+        for recording_file in match_file.players.into_iter().take(2) {
+            loaded.push(replays::load_game(tick_rate, recording_file)?);
+        }
+        let (Some((game_2, replay_2)), Some((game_1, replay_1))) = (loaded.pop(), loaded.pop())
+        else {
+            return Err("a versus replay needs 2 players".to_string());
+        };
+
+        self.load_match([game_1, game_2]);
+        self.players[0].replay = Some(replay_1);
+        self.players[1].replay = Some(replay_2);
+
+        Ok(())
+    }
+
+    pub fn is_showing_replay(&self) -> bool {
+        self.players[0].replay.is_some()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        if self.is_showing_replay() {
+            self.versus.games.iter().all(|g| g.is_finished())
+        } else {
+            self.versus.is_finished()
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true
+    }
+
+    pub fn unpause(&mut self) {
+        self.paused = false
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused
+    }
+
+    // Both recordings, once the match is over.
+    pub fn recording_files(&self, version: u32) -> Result<recording_file::MatchFile, String> {
+        if self.is_showing_replay() {
+            return Err("Sorry, don't have a recording for you.".to_string());
+        }
+        if !self.versus.is_finished() {
+            return Err("Recording is not available while the match is in progress.".to_string());
+        }
+
+        let players = self
+            .versus
+            .games
+            .iter()
+            .map(|g| {
+                recording_file::RecordingFile::new(
+                    version,
+                    g.rules.clone(),
+                    g.recording.clone(),
+                    Some(g.seed),
+                    g.score_points(),
+                    g.score_lines_cleared(),
+                )
+            })
+            .collect();
+
+        Ok(recording_file::MatchFile::new(version, players))
+    }
+
+    pub fn frame_tick(&mut self, prev_sim_at: time::Instant, dt: u64) -> usize {
+        if self.paused || self.is_finished() || self.net_error.is_some() {
+            return 0;
+        }
+
+        let now = time::Instant::now();
+        let mut remainder = now.duration_since(prev_sim_at).as_micros() as u64;
+
+        let mut acc_runs = 0;
+        while remainder >= dt {
+            acc_runs += 1;
+            remainder -= dt;
+
+            if let Some(ref mut lockstep) = self.net {
+                let advanced = lockstep.advance(&mut self.versus);
+                self.net_waiting = advanced == Ok(false);
+                if let Err(e) = advanced {
+                    self.net_error = Some(e);
+                    self.net = None;
+                    break;
+                }
+                if self.net_waiting {
+                    break;
+                }
+
+                let player = &mut self.players[lockstep.player];
+                let gm = &self.versus.games[lockstep.player];
+                for action in player.auto_repeat.tick(dt, gm.gravity) {
+                    lockstep.queue_action(action);
+                }
+                player.game_ticks = lockstep.ticks();
+            } else if self.is_showing_replay() {
+                for (player, gm) in self.players.iter_mut().zip(self.versus.games.iter_mut()) {
+                    if let Some(ref r) = player.replay {
+                        player.replay_action_index =
+                            r.queue_actions(gm, player.replay_action_index, player.game_ticks);
+                    }
+                    player.game_ticks = gm.tick();
+                }
+            } else {
+                for (player, gm) in self.players.iter_mut().zip(self.versus.games.iter_mut()) {
+                    for action in player.auto_repeat.tick(dt, gm.gravity) {
+                        let _ = gm.queue_action(action);
+                    }
+                }
+                let ticks = self.versus.tick();
+                for player in self.players.iter_mut() {
+                    player.game_ticks = ticks;
+                }
+            }
+            // Nothing is shown for the individual game events in versus (yet).
+            for gm in self.versus.games.iter_mut() {
+                gm.drain_events();
+            }
+        }
+        acc_runs
+    }
+
+    // Online every input source controls the local player.
+    fn local_player(&self, player: usize) -> usize {
+        match self.net {
+            Some(ref lockstep) => lockstep.player,
+            None => player,
+        }
+    }
+
+    fn press(&mut self, player: usize, action: tetris::actions::Action) {
+        if self.paused || self.is_finished() || self.is_showing_replay() || self.net_error.is_some()
+        {
+            return;
+        }
+
+        let player = self.local_player(player);
+        match action {
+            tetris::actions::Action::MoveLeft
+            | tetris::actions::Action::MoveRight
+            | tetris::actions::Action::MoveDown => self.players[player].auto_repeat.press(action),
+            _ => match self.net {
+                Some(ref mut lockstep) => lockstep.queue_action(action),
+                None => {
+                    let _ = self.versus.games[player].queue_action(action);
+                }
+            },
+        }
+    }
+
+    fn release(&mut self, player: usize, action: tetris::actions::Action) {
+        let player = self.local_player(player);
+        self.players[player].auto_repeat.release(action);
+    }
+
+    fn controller_player(&self, which: u32) -> Option<usize> {
+        match self.net {
+            Some(ref lockstep) => Some(lockstep.player),
+            None => self
+                .players
+                .iter()
+                .position(|p| p.controller_id == Some(which)),
+        }
+    }
+
+    // A new match after this one, or pausing it. Online matches can't be paused and a
+    // new one needs a new connection.
+    fn start_action(&self) -> Option<actions::Action> {
+        if self.net.is_some() || self.net_error.is_some() {
+            None
+        } else if self.is_finished() {
+            Some(actions::Action::GameNew)
+        } else {
+            Some(actions::Action::TogglePause)
+        }
+    }
+
+    pub fn process_events(&mut self, event_pump: &mut sdl2::EventPump) -> Vec<actions::Action> {
+        let mut ui_actions = vec![];
+
+        for event in event_pump.poll_iter() {
+            match event {
+                event::Event::Quit { .. } => ui_actions.push(actions::Action::Quit),
+                event::Event::KeyDown {
+                    keycode: Some(keycode),
+                    repeat: false,
+                    ..
+                } => match keycode {
+                    keyboard::Keycode::Escape => ui_actions.push(actions::Action::MenuShow),
+                    keyboard::Keycode::Backquote => ui_actions.push(actions::Action::ConsoleShow),
+                    keyboard::Keycode::F11 => ui_actions.push(actions::Action::ToggleFullScreen),
+                    keyboard::Keycode::Space => ui_actions.extend(self.start_action()),
+                    _ => {
+                        if let Some((player, action)) = keyboard_binding(keycode) {
+                            self.press(player, action);
+                        }
+                    }
+                },
+                event::Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if let Some((player, action)) = keyboard_binding(keycode) {
+                        self.release(player, action);
+                    }
+                }
+                event::Event::ControllerButtonDown { which, button, .. } => match button {
+                    controller::Button::Back => ui_actions.push(actions::Action::MenuShow),
+                    controller::Button::Start => ui_actions.extend(self.start_action()),
+                    _ => {
+                        if let (Some(player), Some(action)) =
+                            (self.controller_player(which), controller_binding(button))
+                        {
+                            self.press(player, action);
+                        }
+                    }
+                },
+                event::Event::ControllerButtonUp { which, button, .. } => {
+                    if let (Some(player), Some(action)) =
+                        (self.controller_player(which), controller_binding(button))
+                    {
+                        self.release(player, action);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        ui_actions
+    }
+
+    pub fn render(
+        &mut self,
+        canvas: &mut render::Canvas<video::Window>,
+        prefs: &preferences::Preferences,
+    ) {
+        canvas.set_draw_color(pixels::Color::RGB(0, 0, 0));
+        canvas.clear();
+
+        let label_colour = pixels::Color::RGBA(255, 255, 255, 255);
+        let value_colour = pixels::Color::RGB(0, 255, 0);
+        let bright_red = pixels::Color::RGBA(255, 0, 0, 255);
+        let teal = pixels::Color::RGBA(34, 216, 236, 255);
+
+        let (window_width, window_height) = canvas.window().size();
+        let half_width = window_width / 2;
+
+        for (idx, gm) in self.versus.games.iter().enumerate() {
+            let cell_size =
+                game_shell::cell_size_for_playfield(half_width, window_height, &gm.play_field);
+            let centre_x = (half_width / 2 + idx as u32 * half_width) as i32;
+
+            game_shell::draw_game(canvas, gm, cell_size, centre_x);
+            game_shell::draw_piece(canvas, gm, cell_size, centre_x, &prefs.drop_indicator);
+
+            let start_x = game_shell::well_start_x(centre_x, cell_size, &gm.play_field);
+            let well_right_x = start_x + (gm.play_field.cols as i32 + 3) * cell_size;
+
+            if let Some(kind) = gm.next_queue.front() {
+                game_shell::draw_shape(
+                    canvas,
+                    gm.forms(*kind)[0],
+                    game_shell::piece_colour(gm, *kind),
+                    cell_size / 2,
+                    well_right_x + cell_size,
+                    4 * cell_size,
+                );
+            }
+
+            // The garbage waiting to rise into the well.
+            if gm.incoming_garbage() > 0 {
+                graphics::render_text(
+                    canvas,
+                    &self.label_font,
+                    bright_red,
+                    well_right_x + cell_size,
+                    8 * cell_size,
+                    &format!("+{}", gm.incoming_garbage()),
+                );
+            }
+
+            graphics::render_text(
+                canvas,
+                &self.label_font,
+                label_colour,
+                well_right_x + cell_size,
+                10 * cell_size,
+                &format!("Lines {}", gm.score_lines_cleared),
+            );
+
+            graphics::render_text(
+                canvas,
+                &self.label_font,
+                value_colour,
+                well_right_x + cell_size,
+                12 * cell_size,
+                &format!("{}", gm.score_points),
+            );
+
+            let result = if gm.is_victory() {
+                Some(("WINNER!", teal))
+            } else if gm.is_gameover() {
+                Some(("TOPPED OUT", bright_red))
+            } else {
+                None
+            };
+            if self
+                .net
+                .as_ref()
+                .is_some_and(|lockstep| lockstep.player == idx)
+            {
+                graphics::render_text(
+                    canvas,
+                    &self.label_font,
+                    teal,
+                    well_right_x + cell_size,
+                    14 * cell_size,
+                    "YOU",
+                );
+            }
+
+            if let Some((text, colour)) = result {
+                graphics::render_text_centered(
+                    canvas,
+                    &self.value_font,
+                    colour,
+                    centre_x,
+                    50,
+                    text,
+                );
+            }
+        }
+
+        if let Some(ref e) = self.net_error {
+            graphics::render_text_centered(
+                canvas,
+                &self.label_font,
+                bright_red,
+                half_width as i32,
+                window_height as i32 - 50,
+                e,
+            );
+        } else if self.net_waiting {
+            graphics::render_text_centered(
+                canvas,
+                &self.label_font,
+                label_colour,
+                half_width as i32,
+                window_height as i32 - 50,
+                "Waiting for opponent...",
+            );
+        }
+
+        if self.paused && !self.is_finished() {
+            graphics::render_text_centered(
+                canvas,
+                &self.value_font,
+                bright_red,
+                half_width as i32,
+                50,
+                "PAUSED...",
+            );
+        }
+    }
+}